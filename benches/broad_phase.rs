@@ -0,0 +1,86 @@
+//! The cost of one collision check against the player, by the number of enemies.
+//! Run with `cargo bench --bench broad_phase`.
+use {
+    bevy::prelude::*,
+    dodge_the_bevy::{
+        atlas::SpriteAtlas,
+        broad_phase::SpatialHash,
+        character::Character,
+        hitbox::{Hitbox, Shape},
+    },
+    rand::{rngs::StdRng, Rng, SeedableRng},
+    std::time::{Duration, Instant},
+};
+
+const ROUNDS: u32 = 200;
+
+fn enemies(n: usize, rng: &mut StdRng) -> Vec<(Entity, Character, Hitbox)> {
+    let hitbox = Hitbox::fit(
+        Shape::Capsule {
+            length: 0.5,
+            radius: 0.35,
+        },
+        Vec2::new(132.0, 96.0),
+        true,
+    );
+    (0..n)
+        .map(|i| {
+            let character = Character::from(&SpriteAtlas::default())
+                .with_position(rng.gen_range(-600.0..600.0), rng.gen_range(-400.0..400.0))
+                .with_direction(rng.gen_range(-1.0..1.0), rng.gen_range(-1.0..1.0));
+            (Entity::from_raw(i as u32), character, hitbox)
+        })
+        .collect()
+}
+
+fn time(mut f: impl FnMut() -> usize) -> (Duration, usize) {
+    let start = Instant::now();
+    let mut hits = 0;
+    for _ in 0..ROUNDS {
+        hits = f();
+    }
+    (start.elapsed() / ROUNDS, hits)
+}
+
+fn main() {
+    let mut rng = StdRng::seed_from_u64(0);
+    let player = Hitbox::fit(
+        Shape::Circle { radius: 0.3 },
+        Vec2::new(148.0, 140.0),
+        false,
+    );
+    let player = player.place(&Character::from(&SpriteAtlas::default()));
+    println!(
+        "{:>8} {:>14} {:>14} {:>6}",
+        "enemies", "linear", "spatial hash", "hits"
+    );
+    for n in [100, 1_000, 10_000] {
+        let enemies = enemies(n, &mut rng);
+        let (linear, linear_hits) = time(|| {
+            enemies
+                .iter()
+                .filter(|(_, character, hitbox)| player.overlaps(&hitbox.place(character)))
+                .count()
+        });
+        let mut hash = SpatialHash::default();
+        let (hashed, hashed_hits) = time(|| {
+            hash.clear();
+            for (entity, character, hitbox) in enemies.iter() {
+                let center = Vec2::new(character.trans_x, character.trans_y);
+                hash.insert(*entity, center, hitbox.shape.reach());
+            }
+            hash.query(player.center, player.shape.reach())
+                .iter()
+                .filter(|entity| {
+                    let (_, character, hitbox) = &enemies[entity.index() as usize];
+                    player.overlaps(&hitbox.place(character))
+                })
+                .count()
+        });
+        assert_eq!(linear_hits, hashed_hits);
+        println!(
+            "{:>8} {:>14?} {:>14?} {:>6}",
+            n, linear, hashed, hashed_hits
+        );
+    }
+}
@@ -0,0 +1,179 @@
+use {
+    crate::{
+        enemy::EnemyKind,
+        enemy_table::{EnemyTable, EnemyTableHandle},
+        hitbox::Shape,
+        manifest::GameAssets,
+        player::PLAYER_SPRITES,
+    },
+    bevy::{
+        prelude::*,
+        render::render_resource::{Extent3d, TextureDimension, TextureFormat},
+        utils::HashMap,
+    },
+};
+
+//
+// Texture atlases, built once and shared by all characters of a kind
+//
+#[derive(Clone, Debug, Default)]
+pub struct SpriteAtlas {
+    pub handle: Handle<TextureAtlas>,
+    /// the index of the first sprite
+    pub first: usize,
+    /// the number of frames
+    pub frames: usize,
+    /// of the first sprite in pixels
+    pub size: Vec2,
+}
+
+#[derive(Debug, Default, Resource)]
+pub struct SpriteAtlases {
+    pub player: SpriteAtlas,
+    pub enemies: HashMap<EnemyKind, SpriteAtlas>,
+}
+
+/// The size of a missing sprite, e.g. in headless runs
+const FALLBACK_SIZE: Vec2 = Vec2::new(80.0, 80.0);
+
+/// What stands in for a missing sprite: `shape`, in fractions of `FALLBACK_SIZE` as hitboxes, filled with `color`
+#[derive(Clone, Copy, Debug)]
+pub struct Placeholder {
+    pub shape: Shape,
+    pub color: Color,
+}
+
+pub const PLAYER_PLACEHOLDER: Placeholder = Placeholder {
+    shape: Shape::Circle { radius: 0.45 },
+    color: Color::WHITE,
+};
+
+impl Placeholder {
+    /// in the shape of its hitbox and a colour of its own
+    pub fn for_enemy(kind: EnemyKind, hitbox: Shape) -> Self {
+        // the golden angle keeps neighbouring kinds apart
+        let hue = (kind.0 as f32 * 137.5) % 360.0;
+        Placeholder {
+            shape: hitbox,
+            color: Color::hsl(hue, 0.7, 0.55),
+        }
+    }
+
+    pub fn image(&self) -> Image {
+        let shape = self.shape.fit(FALLBACK_SIZE);
+        let color = self.color.as_rgba_f32().map(|c| (c * 255.0) as u8);
+        let (width, height) = (FALLBACK_SIZE.x as u32, FALLBACK_SIZE.y as u32);
+        let mut data = Vec::with_capacity((4 * width * height) as usize);
+        for y in 0..height {
+            for x in 0..width {
+                let p = Vec2::new(x as f32 + 0.5, y as f32 + 0.5) - 0.5 * FALLBACK_SIZE;
+                data.extend_from_slice(if shape.contains(p) { &color } else { &[0; 4] });
+            }
+        }
+        Image::new(
+            Extent3d {
+                width,
+                height,
+                depth_or_array_layers: 1,
+            },
+            TextureDimension::D2,
+            data,
+            TextureFormat::Rgba8UnormSrgb,
+        )
+    }
+}
+
+/// Pack the sprites named `names` in the asset manifest into an atlas; the first one is the first frame.
+/// A missing image is replaced by `placeholder`, with a warning.
+pub fn build_atlas<'a>(
+    names: impl Iterator<Item = &'a str>,
+    placeholder: Placeholder,
+    assets: &GameAssets,
+    textures: &mut Assets<Image>,
+    texture_atlases: &mut Assets<TextureAtlas>,
+) -> SpriteAtlas {
+    let mut texture_atlas_builder = TextureAtlasBuilder::default();
+    let mut sprite_handles = Vec::new();
+    for name in names {
+        let handle = match assets.sprite(name) {
+            Some(handle) => {
+                if textures.get(handle).is_none() {
+                    warn!("sprite \"{}\" is missing; using a placeholder", name);
+                    textures.set_untracked(handle, placeholder.image());
+                }
+                handle.clone()
+            }
+            None => {
+                warn!(
+                    "sprite \"{}\" is not in the asset manifest; using a placeholder",
+                    name
+                );
+                textures.add(placeholder.image())
+            }
+        };
+        if let Some(image) = textures.get(&handle) {
+            texture_atlas_builder.add_texture(handle.clone_weak(), image);
+        }
+        sprite_handles.push(handle);
+    }
+    let texture_atlas = match texture_atlas_builder.finish(textures) {
+        Ok(texture_atlas) => texture_atlas,
+        Err(e) => {
+            warn!(
+                "failed to build a texture atlas ({}); using a placeholder",
+                e
+            );
+            let mut texture_atlas =
+                TextureAtlas::new_empty(textures.add(placeholder.image()), FALLBACK_SIZE);
+            texture_atlas.add_texture(Rect {
+                min: Vec2::ZERO,
+                max: FALLBACK_SIZE,
+            });
+            texture_atlas
+        }
+    };
+    let first = sprite_handles
+        .first()
+        .and_then(|handle| texture_atlas.get_texture_index(handle))
+        .unwrap_or_default();
+    let frames = texture_atlas.textures.len();
+    let size = texture_atlas
+        .textures
+        .get(first)
+        .map_or(FALLBACK_SIZE, |rect| rect.size());
+    SpriteAtlas {
+        handle: texture_atlases.add(texture_atlas),
+        first,
+        frames,
+        size,
+    }
+}
+
+pub fn build_atlases(
+    assets: Res<GameAssets>,
+    table_handle: Res<EnemyTableHandle>,
+    tables: Res<Assets<EnemyTable>>,
+    mut atlases: ResMut<SpriteAtlases>,
+    mut textures: ResMut<Assets<Image>>,
+    mut texture_atlases: ResMut<Assets<TextureAtlas>>,
+) {
+    atlases.player = build_atlas(
+        PLAYER_SPRITES.iter().copied(),
+        PLAYER_PLACEHOLDER,
+        &assets,
+        &mut textures,
+        &mut texture_atlases,
+    );
+    if let Some(table) = tables.get(&table_handle.0) {
+        for (i, def) in table.kinds.iter().enumerate() {
+            let atlas = build_atlas(
+                def.sprites.iter().map(String::as_str),
+                Placeholder::for_enemy(EnemyKind(i), def.hitbox),
+                &assets,
+                &mut textures,
+                &mut texture_atlases,
+            );
+            atlases.enemies.insert(EnemyKind(i), atlas);
+        }
+    }
+}
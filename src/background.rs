@@ -1,358 +1,129 @@
-use bevy::{
-    core_pipeline::core_2d::Transparent2d,
-    ecs::system::{lifetimeless::SRes, SystemParamItem},
-    prelude::*,
-    render::{
-        mesh::Indices,
-        render_asset::RenderAssets,
-        render_phase::{
-            AddRenderCommand, DrawFunctions, EntityRenderCommand, RenderCommandResult, RenderPhase,
-            SetItemPipeline, TrackedRenderPass,
-        },
-        render_resource::*,
-        renderer::{RenderDevice, RenderQueue},
-        texture::BevyDefault,
-        view::VisibleEntities,
-        RenderApp, RenderStage,
+use {
+    crate::manifest::{GameAssets, BACKGROUND_SHADER},
+    bevy::{
+        prelude::*,
+        reflect::TypeUuid,
+        render::render_resource::{AsBindGroup, ShaderRef},
+        sprite::{Material2d, Material2dPlugin, MaterialMesh2dBundle},
     },
-    sprite::{
-        DrawMesh2d, Mesh2dHandle, Mesh2dPipeline, Mesh2dPipelineKey, Mesh2dUniform,
-        SetMesh2dBindGroup, SetMesh2dViewBindGroup,
-    },
-    utils::FloatOrd,
 };
 
-pub fn setup_background(
-    mut commands: Commands,
-    windows: Res<Windows>,
-    mut meshes: ResMut<Assets<Mesh>>,
-) {
-    let window = windows.get_primary().unwrap();
-    let mut rect = Mesh::new(PrimitiveTopology::TriangleList);
-    let v_pos: Vec<[f32; 3]> = vec![
-        [-1.0, -1.0, 0.0],
-        [-1.0, 1.0, 0.0],
-        [1.0, 1.0, 0.0],
-        [1.0, -1.0, 0.0],
-    ];
-    rect.insert_attribute(Mesh::ATTRIBUTE_POSITION, v_pos);
-    let mut v_color = vec![[0.0, 0.0, 0.0, 1.0]];
-    v_color.extend_from_slice(&[[1.0, 1.0, 0.0, 1.0]; 3]);
-    rect.insert_attribute(Mesh::ATTRIBUTE_COLOR, v_color);
-    let mut indices = vec![0, 1, 4];
-    for i in 2..=4 {
-        indices.extend_from_slice(&[0, i, i - 1]);
-    }
-    rect.set_indices(Some(Indices::U32(indices)));
-    commands.spawn((
-        ColoredMesh2d::default(),
-        Mesh2dHandle(meshes.add(rect)),
-        // Transform::default(),
-        Transform::default().with_scale(Vec3::splat(window.width())),
-        // Transform::default().with_scale(Vec3::splat(128.)),
-        GlobalTransform::default(),
-        Visibility::default(),
-        ComputedVisibility::default(),
-    ));
-}
-
-/// A marker component for colored 2d meshes
-#[derive(Component, Default)]
-pub struct ColoredMesh2d;
-
-/// Custom pipeline for 2d meshes with vertex colors
-#[derive(Resource)]
-pub struct ColoredMesh2dPipeline {
-    shader: Handle<Shader>,
-    /// this pipeline wraps the standard [`Mesh2dPipeline`]
-    mesh2d_pipeline: Mesh2dPipeline,
-    time_bind_group_layout: BindGroupLayout,
+//
+// Background: OKLab blends animated in `assets/shaders/background.wgsl`
+//
+/// The look of the background. Change it through `Background` at any time,
+/// e.g. to give a stage or a game mode its own.
+#[derive(AsBindGroup, TypeUuid, Clone, Debug)]
+#[uuid = "4509d548-94eb-4008-9046-c3e297867882"]
+pub struct BackgroundMaterial {
+    /// in OKLab (see `oklab`): the first two swing into each other at the centre,
+    /// the last two towards the edges
+    #[uniform(0)]
+    pub palette: [Vec3; 4],
+    /// of the swing, in radians per second
+    #[uniform(0)]
+    pub speed: f32,
+    /// where the inner colours are, in fractions of the background from its top left corner
+    #[uniform(0)]
+    pub center: Vec2,
+    /// how soon the outer colours take over away from `center`
+    #[uniform(0)]
+    pub intensity: f32,
 }
 
-impl FromWorld for ColoredMesh2dPipeline {
-    fn from_world(world: &mut World) -> Self {
-        let world = world.cell();
-        let asset_server = world.get_resource::<AssetServer>().unwrap();
-        let shader = asset_server.load("shaders/background.wgsl");
-
-        let render_device = world.get_resource_mut::<RenderDevice>().unwrap();
-        let time_bind_group_layout =
-            render_device.create_bind_group_layout(&BindGroupLayoutDescriptor {
-                label: Some("time bind group"),
-                entries: &[BindGroupLayoutEntry {
-                    binding: 0,
-                    visibility: ShaderStages::FRAGMENT,
-                    ty: BindingType::Buffer {
-                        ty: BufferBindingType::Uniform,
-                        has_dynamic_offset: false,
-                        min_binding_size: BufferSize::new(std::mem::size_of::<f32>() as u64),
-                    },
-                    count: None,
-                }],
-            });
-        let mesh2d_pipeline = world.get_resource::<Mesh2dPipeline>().unwrap().clone();
-
+impl Default for BackgroundMaterial {
+    fn default() -> Self {
         Self {
-            shader,
-            mesh2d_pipeline,
-            time_bind_group_layout,
+            // red, blue, green and white
+            palette: [
+                Vec3::new(0.627955, 0.224863, 0.125846),
+                Vec3::new(0.701674, 0.274566, -0.169156),
+                Vec3::new(0.86644, -0.233887, 0.179498),
+                Vec3::new(1.0, 0.0, 0.0),
+            ],
+            speed: 1.57,
+            center: Vec2::new(0.75, 0.25),
+            intensity: 2.4,
         }
     }
 }
 
-// We implement `SpecializedPipeline` to customize the default rendering from `Mesh2dPipeline`
-impl SpecializedRenderPipeline for ColoredMesh2dPipeline {
-    type Key = Mesh2dPipelineKey;
+/// A copy of the shader named `BACKGROUND_SHADER` in the asset manifest,
+/// as `Material2d` asks for its shader before the manifest is read
+const BACKGROUND_SHADER_HANDLE: HandleUntyped =
+    HandleUntyped::weak_from_u64(Shader::TYPE_UUID, 0x6f3a_1c52_d8e4_9b07);
 
-    fn specialize(&self, key: Self::Key) -> RenderPipelineDescriptor {
-        // Customize how to store the meshes' vertex attributes in the vertex buffer
-        // Our meshes only have position and color
-        let vertex_attributes = vec![
-            VertexAttribute {
-                format: VertexFormat::Float32x3,
-                // this offset is the size of the color attribute, which is stored first
-                offset: 16,
-                // position is available at location 0 in the shader
-                shader_location: 0,
-            },
-            // Color
-            VertexAttribute {
-                format: VertexFormat::Float32x4,
-                offset: 0,
-                shader_location: 1,
-            },
-        ];
-        // This is the sum of the size of position and color attributes (12 + 16 = 28)
-        let vertex_array_stride = 28;
-
-        RenderPipelineDescriptor {
-            vertex: VertexState {
-                // Use our custom shader
-                shader: self.shader.clone(),
-                entry_point: "vertex".into(),
-                shader_defs: Vec::new(),
-                // Use our custom vertex buffer
-                buffers: vec![VertexBufferLayout {
-                    array_stride: vertex_array_stride,
-                    step_mode: VertexStepMode::Vertex,
-                    attributes: vertex_attributes,
-                }],
-            },
-            fragment: Some(FragmentState {
-                // Use our custom shader
-                shader: self.shader.clone(),
-                shader_defs: Vec::new(),
-                entry_point: "fragment".into(),
-                targets: vec![Some(ColorTargetState {
-                    format: TextureFormat::bevy_default(),
-                    blend: Some(BlendState::ALPHA_BLENDING),
-                    write_mask: ColorWrites::ALL,
-                })],
-            }),
-            // Use the two standard uniforms for 2d meshes
-            layout: Some(vec![
-                // Bind group 0 is the view uniform
-                self.mesh2d_pipeline.view_layout.clone(),
-                // Bind group 1 is the mesh uniform
-                self.mesh2d_pipeline.mesh_layout.clone(),
-                self.time_bind_group_layout.clone(),
-            ]),
-            primitive: PrimitiveState {
-                front_face: FrontFace::Ccw,
-                cull_mode: Some(Face::Back),
-                unclipped_depth: false,
-                polygon_mode: PolygonMode::Fill,
-                conservative: false,
-                topology: key.primitive_topology(),
-                strip_index_format: None,
-            },
-            depth_stencil: None,
-            multisample: MultisampleState {
-                count: key.msaa_samples(),
-                mask: !0,
-                alpha_to_coverage_enabled: false,
-            },
-            label: Some("colored_mesh2d_pipeline".into()),
-        }
+impl Material2d for BackgroundMaterial {
+    fn fragment_shader() -> ShaderRef {
+        BACKGROUND_SHADER_HANDLE.typed().into()
     }
 }
 
-// This specifies how to render a colored 2d mesh
-type DrawColoredMesh2d = (
-    // Set the pipeline
-    SetItemPipeline,
-    // Set the view uniform as bind group 0
-    SetMesh2dViewBindGroup<0>,
-    // Set the mesh uniform as bind group 1
-    SetMesh2dBindGroup<1>,
-    // Set the time
-    SetTimeBindGroup<2>,
-    // Draw the mesh
-    DrawMesh2d,
-);
-
-/// Plugin that renders [`ColoredMesh2d`]s
-pub struct ColoredMesh2dPlugin;
+/// `color` in OKLab, for `BackgroundMaterial::palette`
+// the matrices as published, in full
+#[allow(clippy::excessive_precision)]
+pub fn oklab(color: Color) -> Vec3 {
+    let [r, g, b, _] = color.as_linear_rgba_f32();
+    let l = (0.4122214708 * r + 0.5363325363 * g + 0.0514459929 * b).cbrt();
+    let m = (0.2119034982 * r + 0.6806995451 * g + 0.1073969566 * b).cbrt();
+    let s = (0.0883024619 * r + 0.2817188376 * g + 0.6299787005 * b).cbrt();
+    Vec3::new(
+        0.2104542553 * l + 0.7936177850 * m - 0.0040720468 * s,
+        1.9779984951 * l - 2.4285922050 * m + 0.4505937099 * s,
+        0.0259040371 * l + 0.7827717662 * m - 0.8086757660 * s,
+    )
+}
 
-impl Plugin for ColoredMesh2dPlugin {
-    fn build(&self, app: &mut App) {
-        let render_device = app.world.get_resource::<RenderDevice>().unwrap();
-        let buffer = render_device.create_buffer(&BufferDescriptor {
-            label: Some("time uniform buffer"),
-            size: std::mem::size_of::<f32>() as u64,
-            usage: BufferUsages::UNIFORM | BufferUsages::COPY_DST,
-            mapped_at_creation: false,
-        });
+/// The material of the background, to edit its look
+#[derive(Debug, Default, Resource)]
+pub struct Background(pub Handle<BackgroundMaterial>);
 
-        // Register our custom draw function and pipeline, and add our render systems
-        let render_app = app.get_sub_app_mut(RenderApp).unwrap();
-        render_app
-            .add_render_command::<Transparent2d, DrawColoredMesh2d>()
-            .insert_resource(TimeMeta {
-                buffer,
-                bind_group: None,
-            })
-            .init_resource::<ColoredMesh2dPipeline>()
-            .init_resource::<SpecializedRenderPipelines<ColoredMesh2dPipeline>>()
-            .add_system_to_stage(RenderStage::Extract, extract_time)
-            .add_system_to_stage(RenderStage::Extract, extract_colored_mesh2d)
-            .add_system_to_stage(RenderStage::Prepare, prepare_time)
-            .add_system_to_stage(RenderStage::Queue, queue_colored_mesh2d)
-            .add_system_to_stage(RenderStage::Queue, queue_time_bind_group);
-    }
-}
+/// The systems for `DodgePlugin`
+pub struct BackgroundPlugin;
 
-/// Extract the [`ColoredMesh2d`] marker component into the render app
-pub fn extract_colored_mesh2d(
-    mut commands: Commands,
-    mut previous_len: Local<usize>,
-    query: Query<(Entity, &ComputedVisibility), With<ColoredMesh2d>>,
-) {
-    let mut values = Vec::with_capacity(*previous_len);
-    for (entity, computed_visibility) in query.iter() {
-        if !computed_visibility.is_visible() {
-            continue;
-        }
-        values.push((entity, (ColoredMesh2d,)));
+impl Plugin for BackgroundPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_plugin(Material2dPlugin::<BackgroundMaterial>::default())
+            .init_resource::<Background>()
+            .add_system(copy_background_shader);
     }
-    *previous_len = values.len();
-    commands.insert_or_spawn_batch(values);
 }
 
-/// Queue the 2d meshes marked with [`ColoredMesh2d`] using our custom pipeline and draw function
-#[allow(clippy::too_many_arguments)]
-pub fn queue_colored_mesh2d(
-    transparent_draw_functions: Res<DrawFunctions<Transparent2d>>,
-    colored_mesh2d_pipeline: Res<ColoredMesh2dPipeline>,
-    mut pipelines: ResMut<SpecializedRenderPipelines<ColoredMesh2dPipeline>>,
-    mut pipeline_cache: ResMut<PipelineCache>,
-    msaa: Res<Msaa>,
-    render_meshes: Res<RenderAssets<Mesh>>,
-    colored_mesh2d: Query<(&Mesh2dHandle, &Mesh2dUniform), With<ColoredMesh2d>>,
-    mut views: Query<(&VisibleEntities, &mut RenderPhase<Transparent2d>)>,
+/// Follow the shader in the manifest into `BACKGROUND_SHADER_HANDLE`, as it loads or changes
+fn copy_background_shader(
+    assets: Res<GameAssets>,
+    mut shader_events: EventReader<AssetEvent<Shader>>,
+    mut shaders: ResMut<Assets<Shader>>,
 ) {
-    if colored_mesh2d.is_empty() {
-        return;
-    }
-    // Iterate each view (a camera is a view)
-    for (visible_entities, mut transparent_phase) in views.iter_mut() {
-        let draw_colored_mesh2d = transparent_draw_functions
-            .read()
-            .get_id::<DrawColoredMesh2d>()
-            .unwrap();
-
-        let mesh_key = Mesh2dPipelineKey::from_msaa_samples(msaa.samples);
-
-        // Queue all entities visible to that view
-        for visible_entity in &visible_entities.entities {
-            if let Ok((mesh2d_handle, mesh2d_uniform)) = colored_mesh2d.get(*visible_entity) {
-                // Get our specialized pipeline
-                let mut mesh2d_key = mesh_key;
-                if let Some(mesh) = render_meshes.get(&mesh2d_handle.0) {
-                    mesh2d_key |=
-                        Mesh2dPipelineKey::from_primitive_topology(mesh.primitive_topology);
+    let source = match assets.shaders.get(BACKGROUND_SHADER) {
+        Some(handle) => handle,
+        None => return,
+    };
+    for event in shader_events.iter() {
+        if let AssetEvent::Created { handle } | AssetEvent::Modified { handle } = event {
+            if handle == source {
+                if let Some(shader) = shaders.get(handle).cloned() {
+                    shaders.set_untracked(BACKGROUND_SHADER_HANDLE, shader);
                 }
-
-                let pipeline_id =
-                    pipelines.specialize(&mut pipeline_cache, &colored_mesh2d_pipeline, mesh2d_key);
-
-                let mesh_z = mesh2d_uniform.transform.w_axis.z;
-                transparent_phase.add(Transparent2d {
-                    entity: *visible_entity,
-                    draw_function: draw_colored_mesh2d,
-                    pipeline: pipeline_id,
-                    // The 2d render items are sorted according to their z value before rendering,
-                    // in order to get correct transparency
-                    sort_key: FloatOrd(mesh_z),
-                    // This material is not batched
-                    batch_range: None,
-                });
             }
         }
     }
 }
 
-#[derive(Default, Resource)]
-struct ExtractedTime {
-    seconds_since_startup: f32,
-}
-
-// extract the passed time into a resource in the render world
-fn extract_time(mut commands: Commands, time: Res<Time>) {
-    commands.insert_resource(ExtractedTime {
-        seconds_since_startup: time.elapsed_seconds(),
-    });
-}
-
-#[derive(Resource)]
-struct TimeMeta {
-    buffer: Buffer,
-    bind_group: Option<BindGroup>,
-}
-
-// write the extracted time into the corresponding uniform buffer
-fn prepare_time(
-    time: Res<ExtractedTime>,
-    time_meta: ResMut<TimeMeta>,
-    render_queue: Res<RenderQueue>,
-) {
-    render_queue.write_buffer(
-        &time_meta.buffer,
-        0,
-        bevy::core::cast_slice(&[time.seconds_since_startup]),
-    );
-}
-
-// create a bind group for the time uniform buffer
-fn queue_time_bind_group(
-    render_device: Res<RenderDevice>,
-    mut time_meta: ResMut<TimeMeta>,
-    pipeline: Res<ColoredMesh2dPipeline>,
+pub fn setup_background(
+    mut commands: Commands,
+    windows: Res<Windows>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<BackgroundMaterial>>,
+    mut background: ResMut<Background>,
 ) {
-    let bind_group = render_device.create_bind_group(&BindGroupDescriptor {
-        label: None,
-        layout: &pipeline.time_bind_group_layout,
-        entries: &[BindGroupEntry {
-            binding: 0,
-            resource: time_meta.buffer.as_entire_binding(),
-        }],
+    let window = windows.get_primary().unwrap();
+    background.0 = materials.add(BackgroundMaterial::default());
+    commands.spawn(MaterialMesh2dBundle {
+        mesh: meshes.add(shape::Quad::new(Vec2::splat(2.0)).into()).into(),
+        material: background.0.clone(),
+        // larger than the window, so that the edges stay out of sight when the camera shakes
+        transform: Transform::default().with_scale(Vec3::splat(window.width())),
+        ..Default::default()
     });
-    time_meta.bind_group = Some(bind_group);
-}
-
-struct SetTimeBindGroup<const I: usize>;
-impl<const I: usize> EntityRenderCommand for SetTimeBindGroup<I> {
-    type Param = SRes<TimeMeta>;
-
-    fn render<'w>(
-        _view: Entity,
-        _item: Entity,
-        time_meta: SystemParamItem<'w, '_, Self::Param>,
-        pass: &mut TrackedRenderPass<'w>,
-    ) -> RenderCommandResult {
-        let time_bind_group = time_meta.into_inner().bind_group.as_ref().unwrap();
-        pass.set_bind_group(I, time_bind_group, &[]);
-
-        RenderCommandResult::Success
-    }
 }
@@ -0,0 +1,313 @@
+use {
+    crate::{character::Character, player::Player, Arena, SIMULATION_TICK},
+    bevy::{ecs::system::EntityCommands, prelude::*},
+    serde::Deserialize,
+    std::f32::consts::TAU,
+};
+
+//
+// Enemy movement behaviours, added on top of the straight move in `move_enemy`
+//
+/// How a behaviour is written in `EnemyDef::behaviours`
+#[derive(Clone, Debug, Deserialize)]
+pub enum Behaviour {
+    /// sway sideways by `amplitude` pixels, `frequency` times a second
+    Wave { amplitude: f32, frequency: f32 },
+    /// turn toward the player by `turn_rate` radians a second at most
+    Homing { turn_rate: f32 },
+    /// crawl for `wind_up` seconds, then rush `boost` times faster for `duration` seconds
+    Dash {
+        wind_up: f32,
+        duration: f32,
+        boost: f32,
+    },
+    /// reflect off the edges of the arena
+    Bounce,
+}
+
+impl Behaviour {
+    pub fn insert_into(&self, entity: &mut EntityCommands) {
+        match *self {
+            Behaviour::Wave {
+                amplitude,
+                frequency,
+            } => entity.insert(Wave {
+                amplitude,
+                frequency,
+                elapsed: 0.0,
+            }),
+            Behaviour::Homing { turn_rate } => entity.insert(Homing { turn_rate }),
+            Behaviour::Dash {
+                wind_up,
+                duration,
+                boost,
+            } => entity.insert(Dash {
+                wind_up,
+                duration,
+                boost,
+                elapsed: 0.0,
+            }),
+            Behaviour::Bounce => entity.insert(Bounce),
+        };
+    }
+}
+
+/// The behaviour systems, which run after `move_enemy`
+#[derive(Debug, Clone, PartialEq, Eq, Hash, SystemLabel)]
+pub struct EnemyBehaviour;
+
+#[derive(Component, Debug)]
+pub struct Wave {
+    pub amplitude: f32,
+    pub frequency: f32,
+    pub elapsed: f32,
+}
+
+impl Wave {
+    /// the sideways displacement at `t` seconds
+    pub fn offset(&self, t: f32) -> f32 {
+        self.amplitude * (TAU * self.frequency * t).sin()
+    }
+}
+
+#[derive(Component, Debug)]
+pub struct Homing {
+    pub turn_rate: f32,
+}
+
+#[derive(Component, Debug)]
+pub struct Dash {
+    pub wind_up: f32,
+    pub duration: f32,
+    pub boost: f32,
+    pub elapsed: f32,
+}
+
+impl Dash {
+    /// the speed during the wind-up relative to the usual one
+    pub const WIND_UP_SPEED: f32 = 0.25;
+    /// how much faster than usual it moves now
+    pub fn speed_factor(&self) -> f32 {
+        let cycle = self.wind_up + self.duration;
+        if cycle <= 0.0 || self.elapsed % cycle < self.wind_up {
+            Self::WIND_UP_SPEED
+        } else {
+            self.boost
+        }
+    }
+}
+
+#[derive(Component, Debug)]
+pub struct Bounce;
+
+/// Rotate `velocity` toward `to_target` by `max_angle` radians at most
+pub fn turn_toward(velocity: Vec2, to_target: Vec2, max_angle: f32) -> Vec2 {
+    if velocity.length_squared() == 0.0 || to_target.length_squared() == 0.0 {
+        return velocity;
+    }
+    let angle = velocity
+        .angle_between(to_target)
+        .clamp(-max_angle, max_angle);
+    Vec2::from_angle(angle).rotate(velocity)
+}
+
+/// Fold a position back into `-half..=half` and flip the velocity if it went over
+pub fn reflect(position: f32, velocity: f32, half: f32) -> (f32, f32) {
+    if half < position {
+        (2.0 * half - position, -velocity.abs())
+    } else if position < -half {
+        (-2.0 * half - position, velocity.abs())
+    } else {
+        (position, velocity)
+    }
+}
+
+pub fn wave_motion(mut query: Query<(&mut Character, &mut Wave)>) {
+    let dt = SIMULATION_TICK as f32;
+    for (mut enemy, mut wave) in query.iter_mut() {
+        let before = wave.offset(wave.elapsed);
+        wave.elapsed += dt;
+        let shift = wave.offset(wave.elapsed) - before;
+        let normal = Vec2::new(-enemy.diff_y, enemy.diff_x).normalize_or_zero();
+        enemy.trans_x += normal.x * shift;
+        enemy.trans_y += normal.y * shift;
+    }
+}
+
+pub fn homing_motion(
+    player: Query<&Character, With<Player>>,
+    mut query: Query<(&mut Character, &Homing), Without<Player>>,
+) {
+    let player = match player.get_single() {
+        Ok(player) => Vec2::new(player.trans_x, player.trans_y),
+        Err(_) => return,
+    };
+    let dt = SIMULATION_TICK as f32;
+    for (mut enemy, homing) in query.iter_mut() {
+        let to_player = player - Vec2::new(enemy.trans_x, enemy.trans_y);
+        let velocity = turn_toward(
+            Vec2::new(enemy.diff_x, enemy.diff_y),
+            to_player,
+            homing.turn_rate * dt,
+        );
+        enemy.diff_x = velocity.x;
+        enemy.diff_y = velocity.y;
+    }
+}
+
+pub fn dash_motion(mut query: Query<(&mut Character, &mut Dash)>) {
+    let dt = SIMULATION_TICK as f32;
+    for (mut enemy, mut dash) in query.iter_mut() {
+        // `move_enemy` has moved it at the usual speed already
+        let extra = (dash.speed_factor() - 1.0) * dt;
+        enemy.trans_x += enemy.diff_x * extra;
+        enemy.trans_y += enemy.diff_y * extra;
+        dash.elapsed += dt;
+    }
+}
+
+pub fn bounce_motion(arena: Res<Arena>, mut query: Query<&mut Character, With<Bounce>>) {
+    for mut enemy in query.iter_mut() {
+        let (x, dx) = reflect(enemy.trans_x, enemy.diff_x, 0.5 * arena.width);
+        let (y, dy) = reflect(enemy.trans_y, enemy.diff_y, 0.5 * arena.height);
+        enemy.trans_x = x;
+        enemy.trans_y = y;
+        enemy.diff_x = dx;
+        enemy.diff_y = dy;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, crate::atlas::SpriteAtlas};
+
+    const DT: f32 = SIMULATION_TICK as f32;
+
+    fn character(x: f32, y: f32, diff_x: f32, diff_y: f32) -> Character {
+        Character::from(&SpriteAtlas::default())
+            .with_position(x, y)
+            .with_direction(diff_x, diff_y)
+    }
+
+    /// the straight move of `move_enemy`
+    fn step_characters(mut query: Query<&mut Character>) {
+        for mut character in query.iter_mut() {
+            character.step(DT);
+        }
+    }
+
+    fn run(app: &mut App, ticks: usize) {
+        for _ in 0..ticks {
+            app.update();
+        }
+    }
+
+    fn assert_near(actual: f32, expected: f32) {
+        assert!(
+            (actual - expected).abs() < 1e-3,
+            "expected {}, got {}",
+            expected,
+            actual
+        );
+    }
+
+    #[test]
+    fn wave_sways_across_the_heading() {
+        let mut app = App::new();
+        app.add_system(wave_motion);
+        let wave = Wave {
+            amplitude: 40.0,
+            frequency: 1.0,
+            elapsed: 0.0,
+        };
+        let expected = wave.offset(10.0 * DT);
+        let enemy = app
+            .world
+            .spawn((character(0.0, 0.0, 300.0, 0.0), wave))
+            .id();
+        run(&mut app, 10);
+        let enemy = app.world.get::<Character>(enemy).unwrap();
+        assert_near(enemy.trans_x, 0.0);
+        assert_near(enemy.trans_y, expected);
+        assert_near(enemy.diff_x, 300.0);
+        assert_near(enemy.diff_y, 0.0);
+    }
+
+    #[test]
+    fn homing_turns_toward_the_player() {
+        let mut app = App::new();
+        app.add_system(homing_motion);
+        app.world.spawn((character(0.0, 0.0, 0.0, 0.0), Player));
+        let enemy = app
+            .world
+            .spawn((character(100.0, 0.0, 0.0, 100.0), Homing { turn_rate: 1.0 }))
+            .id();
+        // 0.1 radians to the left, by the most it may turn in 6 ticks
+        run(&mut app, 6);
+        let angle = 6.0 * DT;
+        let enemy_character = app.world.get::<Character>(enemy).unwrap();
+        assert_near(enemy_character.diff_x, -100.0 * angle.sin());
+        assert_near(enemy_character.diff_y, 100.0 * angle.cos());
+        // it doesn't turn past the player
+        run(&mut app, 120);
+        let enemy_character = app.world.get::<Character>(enemy).unwrap();
+        assert_near(enemy_character.diff_x, -100.0);
+        assert_near(enemy_character.diff_y, 0.0);
+    }
+
+    #[test]
+    fn dash_rushes_after_its_wind_up() {
+        let mut app = App::new();
+        app.add_system(dash_motion);
+        let enemy = app
+            .world
+            .spawn((
+                character(0.0, 0.0, 60.0, 0.0),
+                Dash {
+                    wind_up: 0.5,
+                    duration: 0.25,
+                    boost: 3.0,
+                    elapsed: 0.0,
+                },
+            ))
+            .id();
+        // slowed down from 1 pixel a tick to a quarter
+        run(&mut app, 29);
+        let dash = app.world.get::<Dash>(enemy).unwrap();
+        assert_near(dash.speed_factor(), Dash::WIND_UP_SPEED);
+        assert_near(
+            app.world.get::<Character>(enemy).unwrap().trans_x,
+            29.0 * (Dash::WIND_UP_SPEED - 1.0),
+        );
+        run(&mut app, 2);
+        let dash = app.world.get::<Dash>(enemy).unwrap();
+        assert_near(dash.speed_factor(), 3.0);
+        // and back to the wind-up once the dash is over
+        run(&mut app, 15);
+        let dash = app.world.get::<Dash>(enemy).unwrap();
+        assert_near(dash.speed_factor(), Dash::WIND_UP_SPEED);
+        assert_near(app.world.get::<Character>(enemy).unwrap().diff_x, 60.0);
+    }
+
+    #[test]
+    fn bounce_reflects_at_the_arena_edge() {
+        let mut app = App::new();
+        app.init_resource::<Arena>()
+            .add_system(step_characters)
+            .add_system(bounce_motion.after(step_characters));
+        // 10 pixels a tick, 5 pixels from the right edge of the 1200-pixel wide arena
+        let enemy = app
+            .world
+            .spawn((character(595.0, 0.0, 600.0, 0.0), Bounce))
+            .id();
+        run(&mut app, 1);
+        let enemy_character = app.world.get::<Character>(enemy).unwrap();
+        assert_near(enemy_character.trans_x, 595.0);
+        assert_near(enemy_character.diff_x, -600.0);
+        run(&mut app, 1);
+        let enemy_character = app.world.get::<Character>(enemy).unwrap();
+        assert_near(enemy_character.trans_x, 585.0);
+        assert_near(enemy_character.trans_y, 0.0);
+        assert_near(enemy_character.diff_y, 0.0);
+    }
+}
@@ -0,0 +1,83 @@
+use {
+    crate::{character::Character, enemy::Enemy, hitbox::Hitbox},
+    bevy::{prelude::*, utils::HashMap},
+};
+
+//
+// Broad phase: a uniform grid of the enemies, so that collision checks only test nearby pairs
+//
+#[derive(Debug, Resource)]
+pub struct SpatialHash {
+    /// the side of a cell in pixels; about the size of the largest hitbox is good
+    pub cell: f32,
+    cells: HashMap<(i32, i32), Vec<Entity>>,
+}
+
+impl Default for SpatialHash {
+    fn default() -> Self {
+        Self::new(128.0)
+    }
+}
+
+impl SpatialHash {
+    pub fn new(cell: f32) -> Self {
+        Self {
+            cell,
+            cells: HashMap::default(),
+        }
+    }
+
+    /// empty the cells, keeping their storage for the next tick
+    pub fn clear(&mut self) {
+        for entities in self.cells.values_mut() {
+            entities.clear();
+        }
+    }
+
+    /// the range of cells covering the circle at `center` of `reach`
+    fn cell_range(&self, center: Vec2, reach: f32) -> (IVec2, IVec2) {
+        let lo = ((center - Vec2::splat(reach)) / self.cell).floor();
+        let hi = ((center + Vec2::splat(reach)) / self.cell).floor();
+        (lo.as_ivec2(), hi.as_ivec2())
+    }
+
+    /// register `entity` in every cell its hitbox touches
+    pub fn insert(&mut self, entity: Entity, center: Vec2, reach: f32) {
+        let (lo, hi) = self.cell_range(center, reach);
+        for x in lo.x..=hi.x {
+            for y in lo.y..=hi.y {
+                self.cells.entry((x, y)).or_default().push(entity);
+            }
+        }
+    }
+
+    /// the entities that may touch the circle at `center` of `reach`, each once
+    pub fn query(&self, center: Vec2, reach: f32) -> Vec<Entity> {
+        let (lo, hi) = self.cell_range(center, reach);
+        let mut found = Vec::new();
+        for x in lo.x..=hi.x {
+            for y in lo.y..=hi.y {
+                if let Some(entities) = self.cells.get(&(x, y)) {
+                    found.extend_from_slice(entities);
+                }
+            }
+        }
+        found.sort_unstable();
+        found.dedup();
+        found
+    }
+}
+
+pub fn build_spatial_hash(
+    mut hash: ResMut<SpatialHash>,
+    query: Query<(Entity, &Character, &Hitbox), With<Enemy>>,
+) {
+    hash.clear();
+    for (entity, enemy, hitbox) in query.iter() {
+        hash.insert(
+            entity,
+            Vec2::new(enemy.trans_x, enemy.trans_y),
+            hitbox.shape.reach(),
+        );
+    }
+}
@@ -1,4 +1,7 @@
-use {crate::CollisionEvent, bevy::prelude::*};
+use {
+    crate::{settings::Settings, CollisionEvent},
+    bevy::prelude::*,
+};
 
 //
 // Camera
@@ -15,10 +18,11 @@ pub fn setup_camera(mut commands: Commands) {
 }
 
 pub fn shake_camera(
+    settings: Res<Settings>,
     mut camera_query: Query<&mut MainCamera>,
     mut collision_event: EventReader<CollisionEvent>,
 ) {
-    if collision_event.iter().next().is_some() {
+    if collision_event.iter().next().is_some() && 0.0 < settings.screen_shake {
         if let Some(mut camera) = camera_query.iter_mut().next() {
             if camera.shaker.is_none() {
                 camera.shaker = Some(20);
@@ -27,11 +31,25 @@ pub fn shake_camera(
     }
 }
 
-pub fn animate_camera(mut query: Query<(&mut Transform, &mut MainCamera)>) {
+/// The tilt is scaled by `Settings::screen_shake`
+pub fn animate_camera(
+    settings: Res<Settings>,
+    mut query: Query<(&mut Transform, &mut MainCamera)>,
+) {
     if let Some((mut trans, mut camera)) = query.iter_mut().next() {
         if let Some(n) = camera.shaker {
-            trans.rotation = Quat::from_rotation_z(n as f32 * 0.05 * std::f32::consts::PI);
+            trans.rotation = Quat::from_rotation_z(
+                settings.screen_shake * n as f32 * 0.05 * std::f32::consts::PI,
+            );
             camera.shaker = n.checked_sub(1);
         }
     }
 }
+
+/// Level the camera at the start of a game, even in the middle of a shake
+pub fn reset_camera(mut query: Query<(&mut Transform, &mut MainCamera)>) {
+    for (mut trans, mut camera) in query.iter_mut() {
+        trans.rotation = Quat::IDENTITY;
+        camera.shaker = None;
+    }
+}
@@ -1,61 +1,91 @@
-use bevy::prelude::*;
+use {
+    crate::{atlas::SpriteAtlas, clock::GameClock},
+    bevy::prelude::*,
+};
 
 // Newtype to use a `Timer` for this screen as a resource
 #[derive(Component, Deref, DerefMut, Resource)]
 pub struct SpawnTimer(pub Timer);
 
+/// Draw characters between the last two simulation ticks instead of at the last one
+#[derive(Debug, Resource)]
+pub struct RenderInterpolation(pub bool);
+
+impl Default for RenderInterpolation {
+    fn default() -> Self {
+        RenderInterpolation(true)
+    }
+}
+
 //
 // Character, autonomous moving objects
 //
+/// `diff_*` are velocities in pixels per second.
+/// `trans_*` are the positions at the latest simulation tick and `prev_*` at the one before.
 #[derive(Component, Debug)]
 pub struct Character {
-    pub texture_atlas: TextureAtlas,
+    /// the number of frames in the sprite's atlas
+    pub frames: usize,
     pub flip: bool,
     pub diff_x: f32,
     pub diff_y: f32,
     pub trans_x: f32,
     pub trans_y: f32,
+    pub prev_x: f32,
+    pub prev_y: f32,
 }
 
 impl Character {
-    pub fn from(texture_atlas: TextureAtlas) -> Self {
+    pub fn from(atlas: &SpriteAtlas) -> Self {
         Self {
-            texture_atlas,
+            frames: atlas.frames,
             flip: false,
             diff_x: 0.0,
             diff_y: 0.0,
             trans_x: 0.0,
             trans_y: 0.0,
+            prev_x: 0.0,
+            prev_y: 0.0,
         }
     }
+    pub fn with_position(mut self, x: f32, y: f32) -> Self {
+        self.warp_to(x, y);
+        self
+    }
     pub fn with_direction(mut self, x: f32, y: f32) -> Self {
         self.diff_x = x;
         self.diff_y = y;
         self
     }
+    /// advance by one simulation tick
+    pub fn step(&mut self, dt: f32) {
+        self.prev_x = self.trans_x;
+        self.prev_y = self.trans_y;
+        self.trans_x += self.diff_x * dt;
+        self.trans_y += self.diff_y * dt;
+    }
+    /// move without leaving a trail to interpolate along
+    pub fn warp_to(&mut self, x: f32, y: f32) {
+        self.trans_x = x;
+        self.trans_y = y;
+        self.prev_x = x;
+        self.prev_y = y;
+    }
 }
 
-// (from 'sprite_sheet')
-#[allow(clippy::type_complexity, dead_code)]
-pub fn animate_character(
-    time: Res<Time>,
-    mut query: Query<(
-        &mut Character,
-        &mut SpawnTimer,
-        &mut Transform,
-        &mut TextureAtlasSprite,
-    )>,
+/// Copy the simulated positions into `Transform`s every rendered frame
+pub fn place_characters(
+    interpolation: Res<RenderInterpolation>,
+    clock: Res<GameClock>,
+    mut query: Query<(&Character, &mut Transform)>,
 ) {
-    for (mut character, mut timer, mut trans, mut sprite) in query.iter_mut() {
-        trans.translation.x += character.diff_x;
-        trans.translation.y += character.diff_y;
-        // memoize the location after moving
-        character.trans_x = trans.translation.x;
-        character.trans_y = trans.translation.y;
-        timer.tick(time.delta());
-        if timer.finished() {
-            sprite.index = (sprite.index + 1) % character.texture_atlas.textures.len();
-            sprite.flip_x = character.flip;
-        }
+    let alpha = if interpolation.0 {
+        clock.overstep()
+    } else {
+        1.0
+    };
+    for (character, mut trans) in query.iter_mut() {
+        trans.translation.x = character.prev_x + (character.trans_x - character.prev_x) * alpha;
+        trans.translation.y = character.prev_y + (character.trans_y - character.prev_y) * alpha;
     }
 }
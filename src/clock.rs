@@ -0,0 +1,78 @@
+use {
+    crate::{AppState, SIMULATION_TICK},
+    bevy::{ecs::schedule::ShouldRun, prelude::*, time::TimeSystem, utils::Duration},
+};
+
+//
+// Game clock
+//
+/// The time `SimulationStage` runs by. It advances in `AppState::Game` only and
+/// starts over with every run, so the ticks of a run fall on the same frames
+/// however long the menus were open.
+#[derive(Debug, Default, Resource)]
+pub struct GameClock {
+    /// the seconds it advanced in this frame
+    delta: f32,
+    /// the seconds not simulated yet
+    accumulator: f64,
+    /// the delta of this frame instead of `Time`'s
+    fed: Option<f32>,
+}
+
+impl GameClock {
+    pub fn delta(&self) -> Duration {
+        Duration::from_secs_f32(self.delta)
+    }
+    pub fn delta_seconds(&self) -> f32 {
+        self.delta
+    }
+    /// advance by `delta` in this frame instead of by `Time`, e.g. in a replay
+    pub fn feed(&mut self, delta: f32) {
+        self.fed = Some(delta);
+    }
+    /// how far it is from the last tick toward the next, from 0 to 1
+    pub fn overstep(&self) -> f32 {
+        (self.accumulator / SIMULATION_TICK).min(1.0) as f32
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, SystemLabel)]
+pub struct GameClockSystem;
+
+/// The systems for `GameCorePlugin`
+pub struct GameClockPlugin;
+
+impl Plugin for GameClockPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<GameClock>()
+            .add_system_to_stage(
+                CoreStage::First,
+                advance_game_clock.label(GameClockSystem).after(TimeSystem),
+            )
+            .add_system_set(SystemSet::on_enter(AppState::Game).with_system(reset_game_clock));
+    }
+}
+
+/// The run criteria of `SimulationStage`: once for every `SIMULATION_TICK` passed
+pub fn simulation_tick(mut clock: ResMut<GameClock>) -> ShouldRun {
+    if SIMULATION_TICK <= clock.accumulator {
+        clock.accumulator -= SIMULATION_TICK;
+        ShouldRun::YesAndCheckAgain
+    } else {
+        ShouldRun::No
+    }
+}
+
+fn advance_game_clock(state: Res<State<AppState>>, time: Res<Time>, mut clock: ResMut<GameClock>) {
+    let fed = clock.fed.take();
+    clock.delta = match fed {
+        _ if *state.current() != AppState::Game => 0.0,
+        Some(delta) => delta,
+        None => time.delta_seconds(),
+    };
+    clock.accumulator += clock.delta as f64;
+}
+
+fn reset_game_clock(mut clock: ResMut<GameClock>) {
+    *clock = GameClock::default();
+}
@@ -1,39 +1,137 @@
 use {
-    crate::{enemy::Enemy, player::Player, CollisionEvent, GameOverEvent},
-    bevy::{prelude::*, sprite::collide_aabb::collide},
+    crate::{
+        broad_phase::SpatialHash,
+        character::Character,
+        damage::{GameMode, Vitality},
+        enemy::Enemy,
+        hitbox::Hitbox,
+        player::Player,
+        session::GameSession,
+        sound::SoundEffect,
+        CollisionEvent, GameOverEvent,
+    },
+    bevy::prelude::*,
 };
 
 //
 // Collision detection
 //
+#[derive(Debug, Resource)]
+pub struct HitConfig {
+    /// seconds without damage after a hit
+    pub invulnerability: f32,
+    /// seconds per blink while invulnerable
+    pub blink_interval: f32,
+    /// the initial speed of the push away from the enemy, in pixels per second
+    pub knockback: f32,
+    /// how fast the push fades out, per second
+    pub knockback_damping: f32,
+}
+
+impl Default for HitConfig {
+    fn default() -> Self {
+        Self {
+            invulnerability: 1.5,
+            blink_interval: 0.1,
+            knockback: 900.0,
+            knockback_damping: 10.0,
+        }
+    }
+}
+
+/// The player's state after a hit
+#[derive(Component, Debug, Default)]
+pub struct HitRecovery {
+    /// seconds left without damage
+    pub invulnerable: f32,
+    /// in pixels per second, on top of the player's own move
+    pub knockback: Vec2,
+}
+
+impl HitRecovery {
+    pub fn is_invulnerable(&self) -> bool {
+        0.0 < self.invulnerable
+    }
+
+    /// advance by one simulation tick, pushing `player` back
+    pub fn step(&mut self, player: &mut Character, config: &HitConfig, dt: f32) {
+        player.trans_x += self.knockback.x * dt;
+        player.trans_y += self.knockback.y * dt;
+        self.knockback *= (-config.knockback_damping * dt).exp();
+        self.invulnerable = (self.invulnerable - dt).max(0.0);
+    }
+}
+
+/// Only the enemies near the player in `SpatialHash` are tested.
+/// An enemy hits once; it is spent until `recycle_enemies` sends it back.
+#[allow(clippy::type_complexity, clippy::too_many_arguments)]
 pub fn check_collision(
-    mut player_query: Query<(&Transform, &mut Player)>,
-    collider_query: Query<(&Transform, &Enemy)>,
-    asset_server: Res<AssetServer>,
-    audio: Res<Audio>,
+    config: Res<HitConfig>,
+    mode: Res<GameMode>,
+    hash: Res<SpatialHash>,
+    mut session: ResMut<GameSession>,
+    mut player_query: Query<(&Character, &Hitbox, &mut Vitality, &mut HitRecovery), With<Player>>,
+    mut collider_query: Query<(&Character, &Hitbox, &mut Enemy), Without<Player>>,
     mut collision_channel: EventWriter<CollisionEvent>,
     mut game_over_channel: EventWriter<GameOverEvent>,
 ) {
-    let (player_trans, mut player) = player_query.single_mut();
-    // let player_size = player_trans.scale.truncate();
-    for (enemy_trans, enemy) in collider_query.iter() {
-        if enemy.collided {
+    let (player_char, player_hitbox, mut vitality, mut recovery) = player_query.single_mut();
+    if recovery.is_invulnerable() {
+        return;
+    }
+    let player_collider = player_hitbox.place(player_char);
+    for entity in hash.query(player_collider.center, player_hitbox.shape.reach()) {
+        let (enemy_char, enemy_hitbox, mut enemy) = match collider_query.get_mut(entity) {
+            Ok(found) => found,
+            Err(_) => continue,
+        };
+        if enemy.collided || !player_collider.overlaps(&enemy_hitbox.place(enemy_char)) {
             continue;
         }
-        if let Some(_collision) = collide(
-            player_trans.translation,
-            Vec2::new(40.0, 40.0), // player_size,
-            enemy_trans.translation,
-            Vec2::new(40.0, 40.0), // enemy_trans.scale.truncate(),
-        ) {
-            collision_channel.send(CollisionEvent);
-            player.score *= 0.5;
-            if player.score < 1.0 {
-                // should be game over by shifting to the next stage
-                game_over_channel.send(GameOverEvent);
-            } else {
-                audio.play(asset_server.get_handle("sounds/laserpew.ogg"));
-            }
+        let position = Vec2::new(enemy_char.trans_x, enemy_char.trans_y);
+        enemy.collided = true;
+        recovery.invulnerable = config.invulnerability;
+        recovery.knockback = (player_collider.center - position)
+            .try_normalize()
+            .unwrap_or(Vec2::Y)
+            * config.knockback;
+        collision_channel.send(CollisionEvent {
+            kind: enemy.kind,
+            position,
+        });
+        if mode.take_hit(&mut session, &mut vitality, enemy.damage) {
+            // should be game over by shifting to the next stage
+            game_over_channel.send(GameOverEvent);
+        }
+        break;
+    }
+}
+
+/// Fade the player in and out while invulnerable
+pub fn blink_player(
+    config: Res<HitConfig>,
+    mut query: Query<(&HitRecovery, &mut TextureAtlasSprite), With<Player>>,
+) {
+    for (recovery, mut sprite) in query.iter_mut() {
+        let faded = recovery.is_invulnerable()
+            && (recovery.invulnerable / config.blink_interval) as u32 % 2 == 1;
+        sprite.color.set_a(if faded { 0.25 } else { 1.0 });
+    }
+}
+
+pub fn play_hit_sound(
+    mode: Res<GameMode>,
+    session: Res<GameSession>,
+    player_query: Query<&Vitality, With<Player>>,
+    mut collision_event: EventReader<CollisionEvent>,
+    mut sound_effects: EventWriter<SoundEffect>,
+) {
+    for _ in collision_event.iter() {
+        if player_query
+            .iter()
+            .any(|vitality| mode.is_alive(&session, vitality))
+        {
+            sound_effects.send(SoundEffect("hit"));
         }
     }
 }
@@ -0,0 +1,239 @@
+use {
+    crate::{
+        camera::MainCamera,
+        character::Character,
+        player::{Player, PLAYER_SPEED},
+        settings::Settings,
+        AppState, Arena, PlayerInput,
+    },
+    bevy::{input::touch::Touches, prelude::*},
+    serde::{Deserialize, Serialize},
+};
+
+//
+// Control schemes
+//
+#[derive(Clone, Copy, Debug, Default, Deserialize, PartialEq, Eq, Resource, Serialize)]
+pub enum ControlScheme {
+    /// the player follows the pointer, or a finger on touch screens
+    #[default]
+    Mouse,
+    /// arrow keys or WASD
+    Keyboard,
+    /// the left stick or the D-pad of the first gamepad
+    Gamepad,
+}
+
+impl ControlScheme {
+    pub fn next(self) -> Self {
+        match self {
+            ControlScheme::Mouse => ControlScheme::Keyboard,
+            ControlScheme::Keyboard => ControlScheme::Gamepad,
+            ControlScheme::Gamepad => ControlScheme::Mouse,
+        }
+    }
+}
+
+/// Keyboard and gamepad steer toward a virtual pointer this far (in pixels) from the player
+const REACH: f32 = 100.0;
+
+/// The state of touch steering in `ControlScheme::Mouse`
+#[derive(Debug, Default, Resource)]
+pub struct TouchSteering {
+    /// follow the finger's movement since it touched instead of the finger itself,
+    /// so that the finger doesn't cover the player
+    pub relative: bool,
+    /// the first active touch, which steers the player
+    touch: Option<u64>,
+    /// from the finger to the target
+    offset: Vec2,
+}
+
+/// The systems for `DodgePlugin`. `Tab` switches the scheme.
+pub struct ControlPlugin;
+
+impl Plugin for ControlPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<ControlScheme>()
+            .init_resource::<TouchSteering>()
+            .add_system(switch_control_scheme.before(PlayerInput))
+            .add_system_set(
+                SystemSet::on_update(AppState::Game)
+                    .label(PlayerInput)
+                    .with_system(track_mouse_movement)
+                    .with_system(track_touch.after(track_mouse_movement))
+                    .with_system(track_keyboard)
+                    .with_system(track_gamepad),
+            );
+    }
+}
+
+/// Head toward the point at (`dx`, `dy`) from the player.
+/// It stops within the 10-pixel dead zone.
+pub fn steer(player: &mut Character, dx: f32, dy: f32) {
+    let dist2 = dx.powi(2) + dy.powi(2);
+    if 100.0 < dist2 {
+        let dist = dist2.sqrt();
+        player.flip = dx < 0.0;
+        player.diff_x = PLAYER_SPEED * dx / dist;
+        player.diff_y = PLAYER_SPEED * dy / dist;
+    } else {
+        player.flip = false;
+        player.diff_x = 0.0;
+        player.diff_y = 0.0;
+    }
+}
+
+/// It is saved in `Settings` when there are
+fn switch_control_scheme(
+    keys: Res<Input<KeyCode>>,
+    mut scheme: ResMut<ControlScheme>,
+    settings: Option<ResMut<Settings>>,
+) {
+    if keys.just_pressed(KeyCode::Tab) {
+        *scheme = scheme.next();
+        info!("control scheme: {:?}", *scheme);
+        if let Some(mut settings) = settings {
+            settings.control_scheme = *scheme;
+            if let Err(e) = settings.save() {
+                error!("failed to save settings to {:?}: {}", settings.path, e);
+            }
+        }
+    }
+}
+
+// from Unofficial Bevy Cheat Book 'Convert cursor to world coodinates'
+#[allow(clippy::type_complexity)]
+fn track_mouse_movement(
+    scheme: Res<ControlScheme>,
+    windows: Res<Windows>,
+    mut queries: ParamSet<(
+        Query<&Transform, With<MainCamera>>,
+        Query<&mut Character, With<Player>>,
+    )>,
+) {
+    if *scheme != ControlScheme::Mouse {
+        return;
+    }
+    let window = match windows.get_primary() {
+        Some(window) => window,
+        None => return,
+    };
+    if let Some(position) = window.cursor_position() {
+        let size = Vec2::new(window.width(), window.height());
+        let p = position - size / 2.0;
+        if let Some(camera_transform) = queries.p0().iter().next() {
+            let clicked = camera_transform.compute_matrix() * p.extend(0.0).extend(1.0);
+            let mut q1 = queries.p1();
+            let mut player = q1.single_mut();
+            let dx = clicked.x - player.trans_x;
+            let dy = clicked.y - player.trans_y;
+            steer(&mut player, dx, dy);
+        }
+    }
+}
+
+/// Touch positions are measured from the top left corner of the window
+fn touch_to_world(arena: &Arena, camera: Option<&Transform>, position: Vec2) -> Vec2 {
+    let p = Vec2::new(
+        position.x - arena.width / 2.0,
+        arena.height / 2.0 - position.y,
+    );
+    match camera {
+        Some(trans) => (trans.compute_matrix() * p.extend(0.0).extend(1.0))
+            .truncate()
+            .truncate(),
+        None => p,
+    }
+}
+
+#[allow(clippy::type_complexity)]
+fn track_touch(
+    scheme: Res<ControlScheme>,
+    arena: Res<Arena>,
+    touches: Res<Touches>,
+    mut steering: ResMut<TouchSteering>,
+    mut queries: ParamSet<(
+        Query<&Transform, With<MainCamera>>,
+        Query<&mut Character, With<Player>>,
+    )>,
+) {
+    if *scheme != ControlScheme::Mouse {
+        return;
+    }
+    let camera = queries.p0().iter().next().cloned();
+    let mut q1 = queries.p1();
+    let mut player = match q1.get_single_mut() {
+        Ok(player) => player,
+        Err(_) => return,
+    };
+    if let Some(id) = steering.touch {
+        if touches.get_pressed(id).is_none() {
+            // lifted
+            steering.touch = None;
+            steer(&mut player, 0.0, 0.0);
+        }
+    }
+    if steering.touch.is_none() {
+        if let Some(touch) = touches.iter().min_by_key(|touch| touch.id()) {
+            let finger = touch_to_world(&arena, camera.as_ref(), touch.position());
+            steering.touch = Some(touch.id());
+            steering.offset = if steering.relative {
+                Vec2::new(player.trans_x, player.trans_y) - finger
+            } else {
+                Vec2::ZERO
+            };
+        }
+    }
+    if let Some(touch) = steering.touch.and_then(|id| touches.get_pressed(id)) {
+        let target = touch_to_world(&arena, camera.as_ref(), touch.position()) + steering.offset;
+        let dx = target.x - player.trans_x;
+        let dy = target.y - player.trans_y;
+        steer(&mut player, dx, dy);
+    }
+}
+
+fn track_keyboard(
+    scheme: Res<ControlScheme>,
+    keys: Res<Input<KeyCode>>,
+    mut query: Query<&mut Character, With<Player>>,
+) {
+    if *scheme != ControlScheme::Keyboard {
+        return;
+    }
+    let pressed = |a: KeyCode, b: KeyCode| (keys.pressed(a) || keys.pressed(b)) as i32 as f32;
+    let x = pressed(KeyCode::Right, KeyCode::D) - pressed(KeyCode::Left, KeyCode::A);
+    let y = pressed(KeyCode::Up, KeyCode::W) - pressed(KeyCode::Down, KeyCode::S);
+    for mut player in query.iter_mut() {
+        steer(&mut player, REACH * x, REACH * y);
+    }
+}
+
+fn track_gamepad(
+    scheme: Res<ControlScheme>,
+    gamepads: Res<Gamepads>,
+    axes: Res<Axis<GamepadAxis>>,
+    buttons: Res<Input<GamepadButton>>,
+    mut query: Query<&mut Character, With<Player>>,
+) {
+    if *scheme != ControlScheme::Gamepad {
+        return;
+    }
+    let gamepad = match gamepads.iter().next() {
+        Some(gamepad) => gamepad,
+        None => return,
+    };
+    let axis = |axis_type| {
+        axes.get(GamepadAxis::new(gamepad, axis_type))
+            .unwrap_or(0.0)
+    };
+    let button =
+        |button_type| buttons.pressed(GamepadButton::new(gamepad, button_type)) as i32 as f32;
+    let mut x = axis(GamepadAxisType::LeftStickX);
+    let mut y = axis(GamepadAxisType::LeftStickY);
+    x += button(GamepadButtonType::DPadRight) - button(GamepadButtonType::DPadLeft);
+    y += button(GamepadButtonType::DPadUp) - button(GamepadButtonType::DPadDown);
+    for mut player in query.iter_mut() {
+        steer(&mut player, REACH * x, REACH * y);
+    }
+}
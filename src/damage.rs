@@ -0,0 +1,77 @@
+use {
+    crate::session::GameSession,
+    bevy::prelude::*,
+    serde::{Deserialize, Serialize},
+    std::str::FromStr,
+};
+
+//
+// Damage models, one per game mode
+//
+#[derive(Clone, Copy, Debug, Default, Deserialize, PartialEq, Eq, Resource, Serialize)]
+pub enum GameMode {
+    /// a hit halves the score; the game is over below 1
+    #[default]
+    Classic,
+    /// the game is over after `LIVES` hits
+    Lives,
+    /// a hit takes `EnemyDef::damage` from `MAX_HEALTH`
+    Health,
+}
+
+impl FromStr for GameMode {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "classic" => Ok(GameMode::Classic),
+            "lives" => Ok(GameMode::Lives),
+            "health" => Ok(GameMode::Health),
+            _ => Err(format!("unknown game mode: {}", s)),
+        }
+    }
+}
+
+pub const LIVES: u32 = 3;
+pub const MAX_HEALTH: f32 = 100.0;
+
+/// What is left of the player in `GameMode::Lives` and `GameMode::Health`
+#[derive(Component, Debug)]
+pub struct Vitality {
+    pub lives: u32,
+    pub health: f32,
+}
+
+impl Default for Vitality {
+    fn default() -> Self {
+        Self {
+            lives: LIVES,
+            health: MAX_HEALTH,
+        }
+    }
+}
+
+impl GameMode {
+    /// take a hit of `damage`; true when it was the last one
+    pub fn take_hit(
+        &self,
+        session: &mut GameSession,
+        vitality: &mut Vitality,
+        damage: f32,
+    ) -> bool {
+        session.hits += 1;
+        match self {
+            GameMode::Classic => session.score *= 0.5,
+            GameMode::Lives => vitality.lives = vitality.lives.saturating_sub(1),
+            GameMode::Health => vitality.health = (vitality.health - damage).max(0.0),
+        }
+        !self.is_alive(session, vitality)
+    }
+
+    pub fn is_alive(&self, session: &GameSession, vitality: &Vitality) -> bool {
+        match self {
+            GameMode::Classic => 1.0 <= session.score,
+            GameMode::Lives => 0 < vitality.lives,
+            GameMode::Health => 0.0 < vitality.health,
+        }
+    }
+}
@@ -0,0 +1,162 @@
+use {
+    crate::{pool::EnemyPool, session::GameSession, WaveEvent, SIMULATION_TICK},
+    bevy::{
+        asset::{AssetLoader, BoxedFuture, Error, LoadContext, LoadedAsset},
+        prelude::*,
+        reflect::TypeUuid,
+    },
+    serde::Deserialize,
+    std::collections::HashMap,
+};
+
+//
+// Wave director: the progression of difficulty, defined in `assets/stages.waves.ron`
+//
+pub const WAVE_TABLE_PATH: &str = "stages.waves.ron";
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct WaveDef {
+    /// seconds until the next wave; the last wave lasts forever
+    pub duration: f32,
+    /// or the score to reach for the next wave
+    #[serde(default)]
+    pub until_score: Option<f32>,
+    /// seconds between spawns
+    pub spawn_interval: f32,
+    /// no more spawns while this many enemies are alive
+    pub max_enemies: usize,
+    /// applied to `EnemyDef::speed`
+    pub speed_scale: f32,
+    /// spawn weights by `EnemyDef::name`; empty means `EnemyDef::spawn_weight`
+    #[serde(default)]
+    pub mix: HashMap<String, f32>,
+}
+
+impl Default for WaveDef {
+    /// a flat flow of enemies, used until the table is loaded
+    fn default() -> Self {
+        Self {
+            duration: f32::INFINITY,
+            until_score: None,
+            spawn_interval: 0.55,
+            max_enemies: usize::MAX,
+            speed_scale: 1.0,
+            mix: HashMap::new(),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, TypeUuid)]
+#[uuid = "b547aaaf-6d07-4163-9f5b-357e75681430"]
+pub struct WaveTable {
+    pub waves: Vec<WaveDef>,
+}
+
+#[derive(Debug, Resource)]
+pub struct WaveTableHandle(pub Handle<WaveTable>);
+
+impl FromWorld for WaveTableHandle {
+    fn from_world(world: &mut World) -> Self {
+        let asset_server = world.resource::<AssetServer>();
+        WaveTableHandle(asset_server.load(WAVE_TABLE_PATH))
+    }
+}
+
+#[derive(Default)]
+pub struct WaveTableLoader;
+
+impl AssetLoader for WaveTableLoader {
+    fn load<'a>(
+        &'a self,
+        bytes: &'a [u8],
+        load_context: &'a mut LoadContext,
+    ) -> BoxedFuture<'a, Result<(), Error>> {
+        Box::pin(async move {
+            let table: WaveTable = ron::de::from_bytes(bytes)?;
+            load_context.set_default_asset(LoadedAsset::new(table));
+            Ok(())
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["waves.ron"]
+    }
+}
+
+#[derive(Debug, Default, Resource)]
+pub struct WaveDirector {
+    /// 0-based index into `WaveTable::waves`
+    pub wave: usize,
+    /// a copy of the current `WaveDef`
+    pub current: WaveDef,
+    /// seconds since the wave began
+    pub elapsed: f32,
+    /// seconds since the last spawn
+    pub since_spawn: f32,
+    /// `setup_enemy` should spawn one in this tick
+    pub spawn_due: bool,
+}
+
+impl WaveDirector {
+    /// the weight of an enemy kind in the current wave
+    pub fn weight(&self, name: &str, spawn_weight: f32) -> f32 {
+        if self.current.mix.is_empty() {
+            spawn_weight
+        } else {
+            self.current.mix.get(name).copied().unwrap_or(0.0)
+        }
+    }
+}
+
+/// Start from the first wave
+pub fn reset_waves(
+    mut director: ResMut<WaveDirector>,
+    table_handle: Res<WaveTableHandle>,
+    tables: Res<Assets<WaveTable>>,
+    mut wave_event: EventWriter<WaveEvent>,
+) {
+    *director = WaveDirector::default();
+    if let Some(first) = tables
+        .get(&table_handle.0)
+        .and_then(|table| table.waves.first())
+    {
+        director.current = first.clone();
+    }
+    wave_event.send(WaveEvent(1));
+}
+
+pub fn direct_waves(
+    mut director: ResMut<WaveDirector>,
+    table_handle: Res<WaveTableHandle>,
+    tables: Res<Assets<WaveTable>>,
+    mut session: ResMut<GameSession>,
+    pool: Res<EnemyPool>,
+    mut wave_event: EventWriter<WaveEvent>,
+) {
+    let dt = SIMULATION_TICK as f32;
+    director.elapsed += dt;
+    director.since_spawn += dt;
+    if let Some(table) = tables.get(&table_handle.0) {
+        let score = session.score;
+        let finished = director.current.duration <= director.elapsed
+            || director
+                .current
+                .until_score
+                .is_some_and(|target| target <= score);
+        if finished {
+            if let Some(next) = table.waves.get(director.wave + 1) {
+                director.wave += 1;
+                director.current = next.clone();
+                director.elapsed = 0.0;
+                wave_event.send(WaveEvent(director.wave + 1));
+            }
+        }
+    }
+    session.wave = director.wave + 1;
+    director.spawn_due = director.current.spawn_interval <= director.since_spawn
+        && pool.stats().live < director.current.max_enemies
+        && pool.has_room();
+    if director.spawn_due {
+        director.since_spawn = 0.0;
+    }
+}
@@ -1,202 +1,180 @@
 use {
     crate::{
+        atlas::{build_atlas, Placeholder, SpriteAtlases},
         character::{Character, SpawnTimer},
-        AppState, Z_AXIS,
+        clock::GameClock,
+        director::WaveDirector,
+        enemy_table::{EnemyTable, EnemyTableHandle},
+        hitbox::Hitbox,
+        manifest::GameAssets,
+        pool::EnemyPool,
+        rng::GameRng,
+        Arena, SIMULATION_TICK, SPRITE_SCALE, Z_AXIS,
     },
     bevy::prelude::*,
-    rand::prelude::random,
+    rand::Rng,
 };
 
 //
 // Enemy
 //
-#[derive(Debug)]
-pub enum EnemyKind {
-    Fly,
-    Swim,
-    Walk,
-}
+/// An index into `EnemyTable::kinds`
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct EnemyKind(pub usize);
 
+/// `speed`, `acceleration` and `damage` are copied from the `EnemyDef` at spawn
 #[derive(Component, Debug)]
 pub struct Enemy {
     pub kind: EnemyKind,
     pub collided: bool,
+    pub speed: f32,
+    pub acceleration: f32,
+    pub damage: f32,
 }
 
+#[allow(clippy::too_many_arguments)]
 pub fn setup_enemy(
-    state: ResMut<State<AppState>>,
-    windows: Res<Windows>,
+    director: Res<WaveDirector>,
+    arena: Res<Arena>,
+    mut rng: ResMut<GameRng>,
+    mut pool: ResMut<EnemyPool>,
     mut commands: Commands,
-    asset_server: Res<AssetServer>,
+    assets: Res<GameAssets>,
+    table_handle: Res<EnemyTableHandle>,
+    tables: Res<Assets<EnemyTable>>,
+    mut atlases: ResMut<SpriteAtlases>,
     mut texture_atlases: ResMut<Assets<TextureAtlas>>,
     mut textures: ResMut<Assets<Image>>,
 ) {
-    if *state.current() != AppState::Game {
+    if !director.spawn_due {
         return;
     }
-    let window = windows.get_primary().unwrap();
-    let mut texture_atlas_builder = TextureAtlasBuilder::default();
-    let (kind, sprites) = match (random::<f32>() * 3.0) as usize {
-        1 => (
-            EnemyKind::Swim,
-            [
-                asset_server.get_handle("sprites/enemySwimming_1.png"),
-                asset_server.get_handle("sprites/enemySwimming_2.png"),
-            ],
-        ),
-        2 => (
-            EnemyKind::Walk,
-            [
-                asset_server.get_handle("sprites/enemyWalking_1.png"),
-                asset_server.get_handle("sprites/enemyWalking_2.png"),
-            ],
-        ),
-        _ => (
-            EnemyKind::Fly,
-            [
-                asset_server.get_handle("sprites/enemyFlyingAlt_1.png"),
-                asset_server.get_handle("sprites/enemyFlyingAlt_2.png"),
-            ],
-        ),
+    let table = match tables.get(&table_handle.0) {
+        Some(table) => table,
+        None => return,
     };
-    for handle in sprites {
-        if let Some(image) = textures.get(&handle) {
-            texture_atlas_builder.add_texture(handle.clone_weak(), image);
-        }
-    }
-    let texture_atlas = texture_atlas_builder.finish(&mut textures).unwrap();
-    let vendor_handle = match kind {
-        EnemyKind::Fly => asset_server.get_handle("sprites/enemyFlyingAlt_1.png"),
-        EnemyKind::Swim => asset_server.get_handle("sprites/enemySwimming_1.png"),
-        EnemyKind::Walk => asset_server.get_handle("sprites/enemyWalking_1.png"),
+    let kind = match table.pick(&mut *rng, |def| {
+        director.weight(&def.name, def.spawn_weight)
+    }) {
+        Some(index) => EnemyKind(index),
+        None => return,
     };
-    let vendor_index = texture_atlas.get_texture_index(&vendor_handle).unwrap();
-    let atlas_handle = texture_atlases.add(texture_atlas.clone());
+    let def = &table.kinds[kind.0];
+    let speed = def.speed * director.current.speed_scale;
+    // built in `build_atlases` unless the table was loaded late
+    let atlas = atlases
+        .enemies
+        .entry(kind)
+        .or_insert_with(|| {
+            build_atlas(
+                def.sprites.iter().map(String::as_str),
+                Placeholder::for_enemy(kind, def.hitbox),
+                &assets,
+                &mut textures,
+                &mut texture_atlases,
+            )
+        })
+        .clone();
 
-    let mut px = 0.5 * random::<f32>() * window.width();
-    let mut py = 0.5 * random::<f32>() * window.height();
-    let mut dx;
-    let mut dy;
-    match (random::<f32>() * 4.0) as usize {
-        1 => {
-            px = window.width() * 0.5 - 40.0;
-            dx = -1.0;
-            dy = random::<f32>() - 0.5;
-        }
-        2 => {
-            px = -(window.width() * 0.5 - 40.0);
-            dx = 1.0;
-            dy = random::<f32>() - 0.5;
-        }
-        3 => {
-            py = window.height() * 0.5 - 40.0;
-            dx = random::<f32>() - 0.5;
-            dy = -1.0;
-        }
-        _ => {
-            py = -(window.height() * 0.5 - 40.0);
-            dx = random::<f32>() - 0.5;
-            dy = 1.0;
-        }
-    }
-    const SPEED: f32 = 7.5;
+    let (px, py, mut dx, mut dy) = entry_point(&mut rng, &arena);
     let dist: f32 = (dx.powi(2) + dy.powi(2)).sqrt();
     assert!(dist < 2.0);
-    dx *= SPEED / dist;
-    dy *= SPEED / dist;
-    commands
-        .spawn(SpriteSheetBundle {
-            transform: Transform {
-                translation: Vec3::new(px, py, Z_AXIS),
-                scale: Vec3::splat(0.5),
-                ..Default::default()
-            },
-            sprite: TextureAtlasSprite::new(vendor_index),
-            texture_atlas: atlas_handle,
+    dx *= speed / dist;
+    dy *= speed / dist;
+    let mut enemy = commands.spawn(SpriteSheetBundle {
+        transform: Transform {
+            translation: Vec3::new(px, py, Z_AXIS),
+            scale: Vec3::splat(SPRITE_SCALE),
             ..Default::default()
-        })
-        .insert(SpawnTimer(Timer::from_seconds(0.15, TimerMode::Once)))
-        .insert(Character::from(texture_atlas).with_direction(dx, dy))
+        },
+        sprite: TextureAtlasSprite::new(atlas.first),
+        texture_atlas: atlas.handle.clone(),
+        ..Default::default()
+    });
+    enemy
+        .insert(SpawnTimer(Timer::from_seconds(
+            def.frame_time,
+            TimerMode::Repeating,
+        )))
+        .insert(
+            Character::from(&atlas)
+                .with_position(px, py)
+                .with_direction(dx, dy),
+        )
         .insert(Enemy {
             kind,
             collided: false,
-        });
+            speed,
+            acceleration: def.acceleration,
+            damage: def.damage,
+        })
+        .insert(Hitbox::fit(def.hitbox, atlas.size, true));
+    for behaviour in def.behaviours.iter() {
+        behaviour.insert_into(&mut enemy);
+    }
+    pool.spawned();
+}
+
+/// Leaving the arena is handled by `recycle_enemies`
+pub fn move_enemy(mut query: Query<(&mut Character, &Enemy)>) {
+    for (mut enemy, et) in query.iter_mut() {
+        let acceleration = et.acceleration.powf(SIMULATION_TICK as f32);
+        enemy.step(SIMULATION_TICK as f32);
+        enemy.diff_x *= acceleration;
+        enemy.diff_y *= acceleration;
+    }
 }
 
 // (from 'sprite_sheet')
 #[allow(clippy::type_complexity)]
 pub fn animate_enemy(
-    // mut commands: Commands,
-    windows: Res<Windows>,
-    time: Res<Time>,
-    mut query: Query<(
-        // Entity,
-        &mut Character,
-        &mut SpawnTimer,
-        &mut Transform,
-        &mut TextureAtlasSprite,
-        &mut Enemy,
-    )>,
+    clock: Res<GameClock>,
+    mut query: Query<
+        (
+            &Character,
+            &mut SpawnTimer,
+            &mut Transform,
+            &mut TextureAtlasSprite,
+        ),
+        With<Enemy>,
+    >,
 ) {
-    let window = windows.get_primary().unwrap();
-    for (mut enemy, mut timer, mut trans, mut sprite, mut et) in query.iter_mut() {
-        trans.translation.x += enemy.diff_x;
-        trans.translation.y += enemy.diff_y;
+    for (enemy, mut timer, mut trans, mut sprite) in query.iter_mut() {
         trans.rotation = Quat::from_rotation_z(enemy.diff_y.atan2(enemy.diff_x));
-        enemy.trans_x = trans.translation.x;
-        enemy.trans_y = trans.translation.y;
-        enemy.diff_x *= 1.01;
-        enemy.diff_y *= 1.01;
-        if 0.5 * window.width() < enemy.trans_x.abs() && 0.5 * window.height() < enemy.trans_y.abs()
-        {
-            // commands.entity(ent).despawn();
-
-            let mut px = 0.5 * random::<f32>() * window.width();
-            let mut py = 0.5 * random::<f32>() * window.height();
-            let mut dx;
-            let mut dy;
-            match (random::<f32>() * 4.0) as usize {
-                1 => {
-                    px = window.width() * 0.5 - 40.0;
-                    dx = -1.0;
-                    dy = random::<f32>() - 0.5;
-                }
-                2 => {
-                    px = -(window.width() * 0.5 - 40.0);
-                    dx = 1.0;
-                    dy = random::<f32>() - 0.5;
-                }
-                3 => {
-                    py = window.height() * 0.5 - 40.0;
-                    dx = random::<f32>() - 0.5;
-                    dy = -1.0;
-                }
-                _ => {
-                    py = -(window.height() * 0.5 - 40.0);
-                    dx = random::<f32>() - 0.5;
-                    dy = 1.0;
-                }
-            }
-            let speed: f32 = match et.kind {
-                EnemyKind::Fly => 9.0,
-                EnemyKind::Swim => 6.2,
-                EnemyKind::Walk => 4.0,
-            };
-            let dist: f32 = (dx.powi(2) + dy.powi(2)).sqrt();
-            dx *= speed / dist;
-            dy *= speed / dist;
+        timer.tick(clock.delta());
+        if timer.finished() {
+            sprite.index = (sprite.index + 1) % enemy.frames.max(1);
+        }
+    }
+}
 
-            trans.translation.x = px;
-            trans.translation.y = py;
-            enemy.trans_x = px;
-            enemy.trans_y = py;
-            enemy.diff_x = dx;
-            enemy.diff_y = dy;
-            et.collided = false;
+/// pick a position on one of the four edges and a heading into the arena
+pub fn entry_point(rng: &mut GameRng, arena: &Arena) -> (f32, f32, f32, f32) {
+    let mut px = 0.5 * rng.gen::<f32>() * arena.width;
+    let mut py = 0.5 * rng.gen::<f32>() * arena.height;
+    let dx;
+    let dy;
+    match (rng.gen::<f32>() * 4.0) as usize {
+        1 => {
+            px = arena.width * 0.5 - 40.0;
+            dx = -1.0;
+            dy = rng.gen::<f32>() - 0.5;
         }
-        timer.tick(time.delta());
-        if timer.finished() {
-            sprite.index = (sprite.index + 1) % enemy.texture_atlas.textures.len();
+        2 => {
+            px = -(arena.width * 0.5 - 40.0);
+            dx = 1.0;
+            dy = rng.gen::<f32>() - 0.5;
+        }
+        3 => {
+            py = arena.height * 0.5 - 40.0;
+            dx = rng.gen::<f32>() - 0.5;
+            dy = -1.0;
+        }
+        _ => {
+            py = -(arena.height * 0.5 - 40.0);
+            dx = rng.gen::<f32>() - 0.5;
+            dy = 1.0;
         }
     }
+    (px, py, dx, dy)
 }
@@ -0,0 +1,102 @@
+use {
+    crate::{behaviour::Behaviour, hitbox::Shape},
+    bevy::{
+        asset::{AssetLoader, BoxedFuture, Error, LoadContext, LoadedAsset},
+        prelude::*,
+        reflect::TypeUuid,
+    },
+    rand::Rng,
+    serde::Deserialize,
+};
+
+//
+// Enemy archetypes, defined in `assets/creeps.enemies.ron`
+//
+pub const ENEMY_TABLE_PATH: &str = "creeps.enemies.ron";
+
+#[derive(Debug, Deserialize)]
+pub struct EnemyDef {
+    pub name: String,
+    /// the frames of the animation, by their names in the asset manifest; the first one is shown at spawn
+    pub sprites: Vec<String>,
+    /// seconds per frame
+    pub frame_time: f32,
+    /// in pixels per second when entering the arena
+    pub speed: f32,
+    /// speed gain per second
+    pub acceleration: f32,
+    /// in fractions of the first sprite, turning with the heading
+    pub hitbox: Shape,
+    /// relative frequency to the other kinds
+    pub spawn_weight: f32,
+    /// the health taken by a hit in `GameMode::Health`
+    #[serde(default = "default_damage")]
+    pub damage: f32,
+    /// movements on top of going straight
+    #[serde(default)]
+    pub behaviours: Vec<Behaviour>,
+}
+
+fn default_damage() -> f32 {
+    25.0
+}
+
+#[derive(Debug, Deserialize, TypeUuid)]
+#[uuid = "d68e1280-4b36-482f-911e-d583a8b52f22"]
+pub struct EnemyTable {
+    pub kinds: Vec<EnemyDef>,
+}
+
+impl EnemyTable {
+    /// choose a kind in proportion to `weight`
+    pub fn pick<R: Rng + ?Sized>(
+        &self,
+        rng: &mut R,
+        weight: impl Fn(&EnemyDef) -> f32,
+    ) -> Option<usize> {
+        let weights = self.kinds.iter().map(weight).collect::<Vec<f32>>();
+        let total: f32 = weights.iter().sum();
+        if total <= 0.0 {
+            return None;
+        }
+        let mut target = rng.gen::<f32>() * total;
+        for (i, w) in weights.iter().enumerate() {
+            if target < *w {
+                return Some(i);
+            }
+            target -= w;
+        }
+        weights.iter().rposition(|w| 0.0 < *w)
+    }
+}
+
+#[derive(Debug, Resource)]
+pub struct EnemyTableHandle(pub Handle<EnemyTable>);
+
+impl FromWorld for EnemyTableHandle {
+    fn from_world(world: &mut World) -> Self {
+        let asset_server = world.resource::<AssetServer>();
+        EnemyTableHandle(asset_server.load(ENEMY_TABLE_PATH))
+    }
+}
+
+#[derive(Default)]
+pub struct EnemyTableLoader;
+
+impl AssetLoader for EnemyTableLoader {
+    fn load<'a>(
+        &'a self,
+        bytes: &'a [u8],
+        load_context: &'a mut LoadContext,
+    ) -> BoxedFuture<'a, Result<(), Error>> {
+        Box::pin(async move {
+            let table: EnemyTable = ron::de::from_bytes(bytes)?;
+            load_context.set_default_asset(LoadedAsset::new(table));
+            Ok(())
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["enemies.ron"]
+    }
+}
@@ -0,0 +1,358 @@
+use {
+    crate::{
+        damage::GameMode,
+        manifest::{GameAssets, UI_FONT},
+        replay::ReplayPlayer,
+        session::GameSession,
+        AppState,
+    },
+    bevy::prelude::*,
+    serde::{Deserialize, Serialize},
+    std::{
+        fs, io,
+        path::PathBuf,
+        time::{SystemTime, UNIX_EPOCH},
+    },
+};
+
+//
+// High scores, kept in the platform's data directory
+//
+pub const MAX_ENTRIES: usize = 10;
+const MAX_NAME_LENGTH: usize = 12;
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct HighScoreEntry {
+    pub name: String,
+    pub score: f32,
+    /// seconds since the Unix epoch
+    pub date: u64,
+    pub seed: u64,
+    pub mode: GameMode,
+}
+
+/// The best `MAX_ENTRIES` games, the highest first
+#[derive(Debug, Default, Deserialize, Resource, Serialize)]
+pub struct HighScores {
+    pub entries: Vec<HighScoreEntry>,
+    /// where it is saved; `None` keeps it in memory
+    #[serde(skip)]
+    pub path: Option<PathBuf>,
+}
+
+impl HighScores {
+    /// e.g. `~/.local/share/dodge-the-bevy/high_scores.ron` on Linux
+    pub fn default_path() -> Option<PathBuf> {
+        dirs::data_dir().map(|dir| dir.join("dodge-the-bevy").join("high_scores.ron"))
+    }
+    pub fn load(path: PathBuf) -> io::Result<Self> {
+        let mut scores: HighScores = ron::from_str(&fs::read_to_string(&path)?)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        scores.path = Some(path);
+        Ok(scores)
+    }
+    pub fn save(&self) -> io::Result<()> {
+        let path = match &self.path {
+            Some(path) => path,
+            None => return Ok(()),
+        };
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let text = ron::ser::to_string_pretty(self, ron::ser::PrettyConfig::default())
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        fs::write(path, text)
+    }
+    /// `score` would make it into the table
+    pub fn qualifies(&self, score: f32) -> bool {
+        0.0 < score
+            && (self.entries.len() < MAX_ENTRIES
+                || self.entries.last().is_none_or(|last| last.score < score))
+    }
+    /// put `entry` in its place and return its 0-based rank, or `None` if it didn't make it
+    pub fn insert(&mut self, entry: HighScoreEntry) -> Option<usize> {
+        let rank = self
+            .entries
+            .iter()
+            .position(|e| e.score < entry.score)
+            .unwrap_or(self.entries.len());
+        if MAX_ENTRIES <= rank {
+            return None;
+        }
+        self.entries.insert(rank, entry);
+        self.entries.truncate(MAX_ENTRIES);
+        Some(rank)
+    }
+    pub fn table_text(&self) -> String {
+        let mut text = String::from("High scores\n");
+        for (i, e) in self.entries.iter().enumerate() {
+            text.push_str(&format!(
+                "{:>2}. {:<12} {:0>4.0}  {:?}  {}\n",
+                i + 1,
+                e.name,
+                e.score,
+                e.mode,
+                format_date(e.date)
+            ));
+        }
+        text
+    }
+}
+
+/// `YYYY-MM-DD` in UTC
+fn format_date(secs: u64) -> String {
+    // from Howard Hinnant's `civil_from_days`
+    let z = (secs / 86_400) as i64 + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1_460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + (month <= 2) as i64;
+    format!("{:04}-{:02}-{:02}", year, month, day)
+}
+
+/// A new record waiting for its name
+#[derive(Debug, Default, Resource)]
+pub struct NameEntry {
+    pub pending: Option<HighScoreEntry>,
+}
+
+#[derive(Component, Debug)]
+pub struct HighScoreTable;
+
+#[derive(Component, Debug)]
+pub struct NameEntryLabel;
+
+/// The systems for `DodgePlugin`
+pub struct HighScorePlugin;
+
+impl Plugin for HighScorePlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(load_high_scores())
+            .init_resource::<NameEntry>()
+            .add_system_set(
+                SystemSet::on_enter(AppState::Setup).with_system(setup_high_score_labels),
+            )
+            .add_system_set(SystemSet::on_enter(AppState::Restart).with_system(begin_name_entry))
+            .add_system_set(SystemSet::on_update(AppState::Restart).with_system(type_name))
+            .add_system_set(SystemSet::on_exit(AppState::Restart).with_system(end_name_entry))
+            .add_system_set(
+                SystemSet::on_enter(AppState::HighScores).with_system(show_high_score_table),
+            )
+            .add_system_set(
+                SystemSet::on_exit(AppState::HighScores).with_system(hide_high_score_table),
+            );
+    }
+}
+
+fn load_high_scores() -> HighScores {
+    let path = match HighScores::default_path() {
+        Some(path) => path,
+        None => {
+            warn!("no data directory; high scores won't be saved");
+            return HighScores::default();
+        }
+    };
+    match HighScores::load(path.clone()) {
+        Ok(scores) => scores,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => HighScores {
+            path: Some(path),
+            ..Default::default()
+        },
+        Err(e) => {
+            warn!("failed to load high scores from {:?}: {}", path, e);
+            // keep the broken file out of the way instead of saving over it
+            let backup = path.with_extension("ron.bak");
+            match fs::rename(&path, &backup) {
+                Ok(()) => {
+                    warn!("moved the broken high scores to {:?}", backup);
+                    HighScores {
+                        path: Some(path),
+                        ..Default::default()
+                    }
+                }
+                Err(e) => {
+                    warn!(
+                        "failed to move {:?} aside ({}); high scores won't be saved",
+                        path, e
+                    );
+                    HighScores::default()
+                }
+            }
+        }
+    }
+}
+
+fn setup_high_score_labels(mut commands: Commands, assets: Res<GameAssets>) {
+    let font = assets.font(UI_FONT);
+    commands
+        .spawn(TextBundle {
+            style: Style {
+                display: Display::None,
+                position_type: PositionType::Absolute,
+                position: UiRect {
+                    left: Val::Percent(3.0),
+                    top: Val::Percent(55.0),
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+            text: Text::from_section(
+                "",
+                TextStyle {
+                    font: font.clone(),
+                    font_size: 20.0,
+                    color: Color::WHITE,
+                },
+            ),
+            ..Default::default()
+        })
+        .insert(HighScoreTable);
+    commands
+        .spawn(TextBundle {
+            style: Style {
+                display: Display::None,
+                position_type: PositionType::Absolute,
+                position: UiRect {
+                    left: Val::Percent(30.0),
+                    top: Val::Percent(28.0),
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+            text: Text::from_section(
+                "",
+                TextStyle {
+                    font,
+                    font_size: 32.0,
+                    color: Color::GOLD,
+                },
+            ),
+            ..Default::default()
+        })
+        .insert(NameEntryLabel);
+}
+
+fn prompt(name: &str) -> String {
+    format!("New record! Your name: {}_", name)
+}
+
+#[allow(clippy::type_complexity)]
+fn begin_name_entry(
+    session: Res<GameSession>,
+    mode: Res<GameMode>,
+    scores: Res<HighScores>,
+    replay_player: Option<Res<ReplayPlayer>>,
+    mut entry: ResMut<NameEntry>,
+    mut labels: ParamSet<(
+        Query<(&mut Style, &mut Text), With<HighScoreTable>>,
+        Query<(&mut Style, &mut Text), With<NameEntryLabel>>,
+    )>,
+) {
+    let score = session.max_score;
+    // a replay doesn't set a new record
+    if replay_player.is_none() && scores.qualifies(score) {
+        entry.pending = Some(HighScoreEntry {
+            name: String::new(),
+            score,
+            date: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map_or(0, |d| d.as_secs()),
+            seed: session.seed,
+            mode: *mode,
+        });
+        for (mut style, mut text) in labels.p1().iter_mut() {
+            style.display = Display::Flex;
+            text.sections[0].value = prompt("");
+        }
+    }
+    for (mut style, mut text) in labels.p0().iter_mut() {
+        style.display = Display::Flex;
+        text.sections[0].value = scores.table_text();
+    }
+}
+
+/// Save the pending record under `name`
+fn commit_name(entry: &mut NameEntry, scores: &mut HighScores) {
+    if let Some(mut record) = entry.pending.take() {
+        if record.name.is_empty() {
+            record.name = "anonymous".to_string();
+        }
+        scores.insert(record);
+        if let Err(e) = scores.save() {
+            error!("failed to save high scores to {:?}: {}", scores.path, e);
+        }
+    }
+}
+
+#[allow(clippy::type_complexity)]
+fn type_name(
+    keys: Res<Input<KeyCode>>,
+    mut chars: EventReader<ReceivedCharacter>,
+    mut entry: ResMut<NameEntry>,
+    mut scores: ResMut<HighScores>,
+    mut labels: ParamSet<(
+        Query<&mut Text, With<HighScoreTable>>,
+        Query<(&mut Style, &mut Text), With<NameEntryLabel>>,
+    )>,
+) {
+    let record = match entry.pending.as_mut() {
+        Some(record) => record,
+        None => return,
+    };
+    for c in chars.iter() {
+        if !c.char.is_control() && record.name.chars().count() < MAX_NAME_LENGTH {
+            record.name.push(c.char);
+        }
+    }
+    if keys.just_pressed(KeyCode::Back) {
+        record.name.pop();
+    }
+    let name = record.name.clone();
+    if keys.just_pressed(KeyCode::Return) {
+        commit_name(&mut entry, &mut scores);
+        for mut text in labels.p0().iter_mut() {
+            text.sections[0].value = scores.table_text();
+        }
+        for (mut style, _) in labels.p1().iter_mut() {
+            style.display = Display::None;
+        }
+    } else {
+        for (_, mut text) in labels.p1().iter_mut() {
+            text.sections[0].value = prompt(&name);
+        }
+    }
+}
+
+/// Restarting without pressing `Return` keeps the record too
+#[allow(clippy::type_complexity)]
+fn end_name_entry(
+    mut entry: ResMut<NameEntry>,
+    mut scores: ResMut<HighScores>,
+    mut labels: Query<&mut Style, Or<(With<HighScoreTable>, With<NameEntryLabel>)>>,
+) {
+    commit_name(&mut entry, &mut scores);
+    for mut style in labels.iter_mut() {
+        style.display = Display::None;
+    }
+}
+
+/// For the high scores screen of the menu
+fn show_high_score_table(
+    scores: Res<HighScores>,
+    mut labels: Query<(&mut Style, &mut Text), With<HighScoreTable>>,
+) {
+    for (mut style, mut text) in labels.iter_mut() {
+        style.display = Display::Flex;
+        text.sections[0].value = scores.table_text();
+    }
+}
+
+fn hide_high_score_table(mut labels: Query<&mut Style, With<HighScoreTable>>) {
+    for mut style in labels.iter_mut() {
+        style.display = Display::None;
+    }
+}
@@ -0,0 +1,634 @@
+use {
+    crate::{character::Character, SPRITE_SCALE},
+    bevy::{
+        prelude::*,
+        render::{mesh::Indices, render_resource::PrimitiveTopology},
+        sprite::MaterialMesh2dBundle,
+    },
+    serde::Deserialize,
+    std::f32::consts::PI,
+};
+
+//
+// Hitboxes
+//
+/// The shape of a hitbox, centred on the character and lying along its local x axis.
+/// In `EnemyDef::hitbox` the sizes are fractions of the sprite; in `Hitbox` they are pixels.
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq)]
+pub enum Shape {
+    /// `radius` of the shorter side of the sprite
+    Circle { radius: f32 },
+    /// `length` of the width between the centres of the two ends, `radius` of the height
+    Capsule { length: f32, radius: f32 },
+    /// an oriented box, `width` and `height` of the sprite's
+    Rect { width: f32, height: f32 },
+}
+
+impl Shape {
+    /// turn the fractions of a sprite of `size` pixels into pixels
+    pub fn fit(self, size: Vec2) -> Self {
+        match self {
+            Shape::Circle { radius } => Shape::Circle {
+                radius: radius * size.min_element(),
+            },
+            Shape::Capsule { length, radius } => Shape::Capsule {
+                length: length * size.x,
+                radius: radius * size.y,
+            },
+            Shape::Rect { width, height } => Shape::Rect {
+                width: width * size.x,
+                height: height * size.y,
+            },
+        }
+    }
+
+    /// `p`, from the centre, is inside
+    pub fn contains(&self, p: Vec2) -> bool {
+        match *self {
+            Shape::Circle { radius } => p.length() <= radius,
+            Shape::Capsule { length, radius } => {
+                Vec2::new((p.x.abs() - 0.5 * length).max(0.0), p.y).length() <= radius
+            }
+            Shape::Rect { width, height } => p.x.abs() <= 0.5 * width && p.y.abs() <= 0.5 * height,
+        }
+    }
+
+    /// the farthest distance from the centre to the edge
+    pub fn reach(&self) -> f32 {
+        match *self {
+            Shape::Circle { radius } => radius,
+            Shape::Capsule { length, radius } => 0.5 * length + radius,
+            Shape::Rect { width, height } => 0.5 * Vec2::new(width, height).length(),
+        }
+    }
+
+    /// points along the edge, counterclockwise
+    fn outline(&self) -> Vec<Vec2> {
+        const SEGMENTS: usize = 16;
+        let arc = |center: Vec2, radius: f32, from: f32| {
+            (0..=SEGMENTS).map(move |i| {
+                center + radius * Vec2::from_angle(from + PI * i as f32 / SEGMENTS as f32)
+            })
+        };
+        match *self {
+            Shape::Circle { radius } => arc(Vec2::ZERO, radius, 0.0)
+                .chain(arc(Vec2::ZERO, radius, PI).skip(1))
+                .take(2 * SEGMENTS)
+                .collect(),
+            Shape::Capsule { length, radius } => {
+                let end = Vec2::new(0.5 * length, 0.0);
+                arc(end, radius, -0.5 * PI)
+                    .chain(arc(-end, radius, 0.5 * PI))
+                    .collect()
+            }
+            Shape::Rect { width, height } => {
+                let (x, y) = (0.5 * width, 0.5 * height);
+                vec![
+                    Vec2::new(x, -y),
+                    Vec2::new(x, y),
+                    Vec2::new(-x, y),
+                    Vec2::new(-x, -y),
+                ]
+            }
+        }
+    }
+}
+
+#[derive(Clone, Copy, Component, Debug)]
+pub struct Hitbox {
+    /// in pixels
+    pub shape: Shape,
+    /// turns with the heading like the enemy sprites in `animate_enemy`
+    pub rotates: bool,
+}
+
+impl Hitbox {
+    /// `shape` in fractions of a sprite of `sprite_size`, drawn at `SPRITE_SCALE`
+    pub fn fit(shape: Shape, sprite_size: Vec2, rotates: bool) -> Self {
+        Self {
+            shape: shape.fit(sprite_size * SPRITE_SCALE),
+            rotates,
+        }
+    }
+
+    /// put it where `character` is at the latest simulation tick
+    pub fn place(&self, character: &Character) -> Collider {
+        let axis = if self.rotates {
+            Vec2::new(character.diff_x, character.diff_y)
+                .try_normalize()
+                .unwrap_or(Vec2::X)
+        } else {
+            Vec2::X
+        };
+        Collider {
+            center: Vec2::new(character.trans_x, character.trans_y),
+            axis,
+            shape: self.shape,
+        }
+    }
+}
+
+/// A hitbox in the arena
+#[derive(Clone, Copy, Debug)]
+pub struct Collider {
+    pub center: Vec2,
+    /// the unit vector of the local x axis
+    pub axis: Vec2,
+    pub shape: Shape,
+}
+
+impl Collider {
+    pub fn overlaps(&self, other: &Collider) -> bool {
+        match (self.core(), other.core()) {
+            (Some((a0, a1, ra)), Some((b0, b1, rb))) => segment_distance(a0, a1, b0, b1) <= ra + rb,
+            (Some((a0, a1, ra)), None) => other.distance_to_segment(a0, a1) <= ra,
+            (None, Some((b0, b1, rb))) => self.distance_to_segment(b0, b1) <= rb,
+            (None, None) => !self.separated_from(other),
+        }
+    }
+
+    /// the segment and the radius around it of a circle or a capsule
+    fn core(&self) -> Option<(Vec2, Vec2, f32)> {
+        match self.shape {
+            Shape::Circle { radius } => Some((self.center, self.center, radius)),
+            Shape::Capsule { length, radius } => {
+                let half = 0.5 * length * self.axis;
+                Some((self.center - half, self.center + half, radius))
+            }
+            Shape::Rect { .. } => None,
+        }
+    }
+
+    fn half_extents(&self) -> Vec2 {
+        match self.shape {
+            Shape::Rect { width, height } => 0.5 * Vec2::new(width, height),
+            _ => Vec2::ZERO,
+        }
+    }
+
+    /// into the frame where the box is axis-aligned at the origin
+    fn local(&self, p: Vec2) -> Vec2 {
+        let d = p - self.center;
+        Vec2::new(d.dot(self.axis), d.dot(self.axis.perp()))
+    }
+
+    /// from a box to the segment `a`-`b`; 0 if they cross
+    fn distance_to_segment(&self, a: Vec2, b: Vec2) -> f32 {
+        let half = self.half_extents();
+        let (a, b) = (self.local(a), self.local(b));
+        if segment_crosses_box(a, b, half) {
+            return 0.0;
+        }
+        let corners = [
+            Vec2::new(half.x, half.y),
+            Vec2::new(-half.x, half.y),
+            Vec2::new(-half.x, -half.y),
+            Vec2::new(half.x, -half.y),
+        ];
+        corners
+            .iter()
+            .map(|corner| point_segment_distance(*corner, a, b))
+            .chain([point_box_distance(a, half), point_box_distance(b, half)])
+            .fold(f32::INFINITY, f32::min)
+    }
+
+    /// the separating axis test of two boxes
+    fn separated_from(&self, other: &Collider) -> bool {
+        let corners = |c: &Collider| {
+            let half = c.half_extents();
+            let (x, y) = (half.x * c.axis, half.y * c.axis.perp());
+            [
+                c.center + x + y,
+                c.center - x + y,
+                c.center - x - y,
+                c.center + x - y,
+            ]
+        };
+        let (ca, cb) = (corners(self), corners(other));
+        let axes = [self.axis, self.axis.perp(), other.axis, other.axis.perp()];
+        axes.iter().any(|axis| {
+            let range = |cs: &[Vec2; 4]| {
+                cs.iter()
+                    .map(|c| c.dot(*axis))
+                    .fold((f32::INFINITY, f32::NEG_INFINITY), |(lo, hi), v| {
+                        (lo.min(v), hi.max(v))
+                    })
+            };
+            let ((a_lo, a_hi), (b_lo, b_hi)) = (range(&ca), range(&cb));
+            a_hi < b_lo || b_hi < a_lo
+        })
+    }
+}
+
+fn point_segment_distance(p: Vec2, a: Vec2, b: Vec2) -> f32 {
+    let ab = b - a;
+    let len2 = ab.length_squared();
+    let t = if len2 == 0.0 {
+        0.0
+    } else {
+        ((p - a).dot(ab) / len2).clamp(0.0, 1.0)
+    };
+    p.distance(a + t * ab)
+}
+
+/// from the box of `half` extents at the origin
+fn point_box_distance(p: Vec2, half: Vec2) -> f32 {
+    (p.abs() - half).max(Vec2::ZERO).length()
+}
+
+fn segment_distance(a0: Vec2, a1: Vec2, b0: Vec2, b1: Vec2) -> f32 {
+    let cross = |o: Vec2, p: Vec2, q: Vec2| (p - o).perp_dot(q - o);
+    let crossing =
+        cross(a0, a1, b0) * cross(a0, a1, b1) < 0.0 && cross(b0, b1, a0) * cross(b0, b1, a1) < 0.0;
+    if crossing {
+        return 0.0;
+    }
+    point_segment_distance(a0, b0, b1)
+        .min(point_segment_distance(a1, b0, b1))
+        .min(point_segment_distance(b0, a0, a1))
+        .min(point_segment_distance(b1, a0, a1))
+}
+
+/// clip the segment `a`-`b` by the box of `half` extents at the origin
+fn segment_crosses_box(a: Vec2, b: Vec2, half: Vec2) -> bool {
+    let d = b - a;
+    let (mut enter, mut exit) = (0.0f32, 1.0f32);
+    for (p, dp, h) in [(a.x, d.x, half.x), (a.y, d.y, half.y)] {
+        if dp == 0.0 {
+            if h < p.abs() {
+                return false;
+            }
+        } else {
+            let (t0, t1) = ((-h - p) / dp, (h - p) / dp);
+            enter = enter.max(t0.min(t1));
+            exit = exit.min(t0.max(t1));
+            if exit < enter {
+                return false;
+            }
+        }
+    }
+    true
+}
+
+//
+// Debug overlay, toggled by `F3`
+//
+#[derive(Debug, Default, Resource)]
+pub struct HitboxOverlay {
+    pub visible: bool,
+    material: Handle<ColorMaterial>,
+    meshes: Vec<(Shape, Handle<Mesh>)>,
+}
+
+/// The outline of the parent's hitbox
+#[derive(Component, Debug)]
+pub struct HitboxOutline;
+
+fn outline_mesh(shape: &Shape) -> Mesh {
+    let points = shape.outline();
+    let mut positions = vec![[0.0, 0.0, 0.0]];
+    positions.extend(points.iter().map(|p| [p.x, p.y, 0.0]));
+    let n = points.len() as u32;
+    let indices = (0..n)
+        .flat_map(|i| [0, i + 1, (i + 1) % n + 1])
+        .collect::<Vec<u32>>();
+    let mut mesh = Mesh::new(PrimitiveTopology::TriangleList);
+    mesh.insert_attribute(
+        Mesh::ATTRIBUTE_NORMAL,
+        vec![[0.0, 0.0, 1.0]; positions.len()],
+    );
+    mesh.insert_attribute(Mesh::ATTRIBUTE_UV_0, vec![[0.0, 0.0]; positions.len()]);
+    mesh.insert_attribute(Mesh::ATTRIBUTE_POSITION, positions);
+    mesh.set_indices(Some(Indices::U32(indices)));
+    mesh
+}
+
+pub fn toggle_hitbox_overlay(keys: Res<Input<KeyCode>>, mut overlay: ResMut<HitboxOverlay>) {
+    if keys.just_pressed(KeyCode::F3) {
+        overlay.visible = !overlay.visible;
+    }
+}
+
+pub fn attach_hitbox_outlines(
+    mut commands: Commands,
+    mut overlay: ResMut<HitboxOverlay>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+    query: Query<(Entity, &Hitbox), Added<Hitbox>>,
+) {
+    if overlay.material == Handle::default() {
+        overlay.material = materials.add(ColorMaterial::from(Color::rgba(1.0, 0.1, 0.1, 0.4)));
+    }
+    for (entity, hitbox) in query.iter() {
+        let mesh = match overlay
+            .meshes
+            .iter()
+            .find(|(shape, _)| *shape == hitbox.shape)
+        {
+            Some((_, mesh)) => mesh.clone(),
+            None => {
+                let mesh = meshes.add(outline_mesh(&hitbox.shape));
+                overlay.meshes.push((hitbox.shape, mesh.clone()));
+                mesh
+            }
+        };
+        let outline = commands
+            .spawn(MaterialMesh2dBundle {
+                mesh: mesh.into(),
+                material: overlay.material.clone(),
+                // undo the sprite's scale, and stay in front of it
+                transform: Transform {
+                    translation: Vec3::new(0.0, 0.0, 0.1),
+                    scale: Vec3::splat(1.0 / SPRITE_SCALE),
+                    ..Default::default()
+                },
+                visibility: Visibility {
+                    is_visible: overlay.visible,
+                },
+                ..Default::default()
+            })
+            .insert(HitboxOutline)
+            .id();
+        commands.entity(entity).add_child(outline);
+    }
+}
+
+pub fn show_hitbox_outlines(
+    overlay: Res<HitboxOverlay>,
+    mut query: Query<&mut Visibility, With<HitboxOutline>>,
+) {
+    if overlay.is_changed() {
+        for mut visibility in query.iter_mut() {
+            visibility.is_visible = overlay.visible;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, crate::atlas::SpriteAtlas, std::f32::consts::FRAC_PI_4};
+
+    /// how far past touching the shapes are moved either way
+    const EPSILON: f32 = 0.01;
+
+    fn collider(x: f32, y: f32, angle: f32, shape: Shape) -> Collider {
+        Collider {
+            center: Vec2::new(x, y),
+            axis: Vec2::from_angle(angle),
+            shape,
+        }
+    }
+
+    fn circle(x: f32, y: f32, radius: f32) -> Collider {
+        collider(x, y, 0.0, Shape::Circle { radius })
+    }
+
+    /// `other` overlaps `fixed` at distance `d - EPSILON` along `dir` but not at `d + EPSILON`
+    fn assert_touches_at(fixed: &Collider, other: impl Fn(Vec2) -> Collider, dir: Vec2, d: f32) {
+        let near = other((d - EPSILON) * dir);
+        let far = other((d + EPSILON) * dir);
+        assert!(fixed.overlaps(&near), "{:?} misses {:?}", fixed, near);
+        assert!(near.overlaps(fixed), "{:?} misses {:?}", near, fixed);
+        assert!(!fixed.overlaps(&far), "{:?} hits {:?}", fixed, far);
+        assert!(!far.overlaps(fixed), "{:?} hits {:?}", far, fixed);
+    }
+
+    #[test]
+    fn circles_touch_at_the_sum_of_their_radii() {
+        let fixed = circle(0.0, 0.0, 10.0);
+        assert_touches_at(&fixed, |p| circle(p.x, p.y, 5.0), Vec2::X, 15.0);
+        assert_touches_at(
+            &fixed,
+            |p| circle(p.x, p.y, 5.0),
+            Vec2::ONE.normalize(),
+            15.0,
+        );
+    }
+
+    #[test]
+    fn circle_and_capsule() {
+        let capsule = collider(
+            0.0,
+            0.0,
+            0.0,
+            Shape::Capsule {
+                length: 40.0,
+                radius: 5.0,
+            },
+        );
+        // past an end, and along the side
+        assert_touches_at(&capsule, |p| circle(p.x, p.y, 10.0), Vec2::X, 35.0);
+        assert_touches_at(&capsule, |p| circle(10.0 + p.x, p.y, 10.0), Vec2::Y, 15.0);
+        // turned a quarter, its side faces the x axis
+        let turned = collider(
+            0.0,
+            0.0,
+            2.0 * FRAC_PI_4,
+            Shape::Capsule {
+                length: 40.0,
+                radius: 5.0,
+            },
+        );
+        assert_touches_at(&turned, |p| circle(p.x, p.y, 10.0), Vec2::X, 15.0);
+        assert_touches_at(&turned, |p| circle(p.x, p.y, 10.0), Vec2::Y, 35.0);
+    }
+
+    #[test]
+    fn circle_and_rotated_rect() {
+        let rect = Shape::Rect {
+            width: 40.0,
+            height: 20.0,
+        };
+        let fixed = collider(0.0, 0.0, 2.0 * FRAC_PI_4, rect);
+        assert_touches_at(&fixed, |p| circle(p.x, p.y, 5.0), Vec2::X, 15.0);
+        assert_touches_at(&fixed, |p| circle(p.x, p.y, 5.0), Vec2::Y, 25.0);
+        // at a corner of the box turned by 45 degrees
+        let diagonal = collider(0.0, 0.0, FRAC_PI_4, rect);
+        let corner = Vec2::new(20.0, 10.0).length();
+        let to_corner = Vec2::from_angle(FRAC_PI_4).rotate(Vec2::new(20.0, 10.0)) / corner;
+        assert_touches_at(
+            &diagonal,
+            |p| circle(p.x, p.y, 5.0),
+            to_corner,
+            corner + 5.0,
+        );
+    }
+
+    #[test]
+    fn capsule_and_rotated_rect() {
+        let fixed = collider(
+            0.0,
+            0.0,
+            2.0 * FRAC_PI_4,
+            Shape::Rect {
+                width: 40.0,
+                height: 20.0,
+            },
+        );
+        let capsule = |p: Vec2| {
+            collider(
+                p.x,
+                p.y,
+                0.0,
+                Shape::Capsule {
+                    length: 30.0,
+                    radius: 5.0,
+                },
+            )
+        };
+        // end on, and side on
+        assert_touches_at(&fixed, capsule, Vec2::X, 10.0 + 15.0 + 5.0);
+        assert_touches_at(&fixed, capsule, Vec2::Y, 20.0 + 5.0);
+    }
+
+    #[test]
+    fn capsules_side_by_side_and_end_to_end() {
+        let capsule = |p: Vec2| {
+            collider(
+                p.x,
+                p.y,
+                0.0,
+                Shape::Capsule {
+                    length: 40.0,
+                    radius: 5.0,
+                },
+            )
+        };
+        let fixed = capsule(Vec2::ZERO);
+        // parallel segments
+        assert_touches_at(&fixed, capsule, Vec2::Y, 10.0);
+        assert_touches_at(&fixed, |p| capsule(p + Vec2::new(25.0, 0.0)), Vec2::Y, 10.0);
+        // on the same line
+        assert_touches_at(&fixed, capsule, Vec2::X, 50.0);
+    }
+
+    #[test]
+    fn separating_axis_test_at_45_degrees() {
+        let square = Shape::Rect {
+            width: 20.0,
+            height: 20.0,
+        };
+        let fixed = collider(0.0, 0.0, 0.0, square);
+        // a corner of the turned square points at a side of the other one
+        let half_diagonal = 10.0 * 2f32.sqrt();
+        assert_touches_at(
+            &fixed,
+            |p| collider(p.x, p.y, FRAC_PI_4, square),
+            Vec2::X,
+            10.0 + half_diagonal,
+        );
+        // a side faces a corner; their bounding boxes overlap, but only the turned square's
+        // own axes tell them apart
+        assert_touches_at(
+            &fixed,
+            |p| collider(p.x, p.y, FRAC_PI_4, square),
+            Vec2::ONE.normalize(),
+            half_diagonal + 10.0,
+        );
+        assert!(!fixed.overlaps(&collider(18.0, 18.0, FRAC_PI_4, square)));
+    }
+
+    #[test]
+    fn segment_distance_of_parallel_and_degenerate_segments() {
+        let (a0, a1) = (Vec2::new(-10.0, 0.0), Vec2::new(10.0, 0.0));
+        // parallel, side by side and beyond the ends
+        assert_eq!(
+            segment_distance(a0, a1, Vec2::new(-5.0, 3.0), Vec2::new(5.0, 3.0)),
+            3.0
+        );
+        assert_eq!(
+            segment_distance(a0, a1, Vec2::new(13.0, 4.0), Vec2::new(30.0, 4.0)),
+            5.0
+        );
+        // collinear and overlapping
+        assert_eq!(
+            segment_distance(a0, a1, Vec2::new(5.0, 0.0), Vec2::new(20.0, 0.0)),
+            0.0
+        );
+        // points
+        let (p, q) = (Vec2::new(1.0, 1.0), Vec2::new(4.0, 5.0));
+        assert_eq!(segment_distance(p, p, q, q), 5.0);
+        assert_eq!(segment_distance(p, p, a0, a1), 1.0);
+        assert_eq!(segment_distance(a0, a1, p, p), 1.0);
+        assert_eq!(point_segment_distance(q, p, p), 5.0);
+    }
+
+    #[test]
+    fn fit_scales_each_shape_by_its_own_sides() {
+        let size = Vec2::new(80.0, 60.0);
+        assert_eq!(
+            Shape::Circle { radius: 0.4 }.fit(size),
+            Shape::Circle { radius: 24.0 }
+        );
+        assert_eq!(
+            Shape::Capsule {
+                length: 0.5,
+                radius: 0.25
+            }
+            .fit(size),
+            Shape::Capsule {
+                length: 40.0,
+                radius: 15.0
+            }
+        );
+        assert_eq!(
+            Shape::Rect {
+                width: 0.75,
+                height: 0.5
+            }
+            .fit(size),
+            Shape::Rect {
+                width: 60.0,
+                height: 30.0
+            }
+        );
+    }
+
+    #[test]
+    fn place_follows_the_character_and_its_heading() {
+        let shape = Shape::Rect {
+            width: 0.5,
+            height: 0.25,
+        };
+        // 200x80 pixels drawn at `SPRITE_SCALE`
+        let size = Vec2::new(200.0, 80.0);
+        let (width, height) = (0.5 * 200.0 * SPRITE_SCALE, 0.25 * 80.0 * SPRITE_SCALE);
+        assert_eq!(
+            Hitbox::fit(shape, size, true).shape,
+            Shape::Rect { width, height }
+        );
+        let moving_down = Character::from(&SpriteAtlas::default())
+            .with_position(100.0, 50.0)
+            .with_direction(0.0, -30.0);
+
+        let turning = Hitbox::fit(shape, size, true).place(&moving_down);
+        assert_eq!(turning.center, Vec2::new(100.0, 50.0));
+        assert_eq!(turning.axis, Vec2::new(0.0, -1.0));
+        let dot = |p: Vec2| circle(p.x, p.y, 1.0);
+        assert_touches_at(
+            &turning,
+            |p| dot(p + turning.center),
+            Vec2::X,
+            0.5 * height + 1.0,
+        );
+        assert_touches_at(
+            &turning,
+            |p| dot(p + turning.center),
+            Vec2::Y,
+            0.5 * width + 1.0,
+        );
+
+        let upright = Hitbox::fit(shape, size, false).place(&moving_down);
+        assert_eq!(upright.axis, Vec2::X);
+        assert_touches_at(
+            &upright,
+            |p| dot(p + upright.center),
+            Vec2::X,
+            0.5 * width + 1.0,
+        );
+
+        // standing still, it lies along the x axis
+        let still = Character::from(&SpriteAtlas::default()).with_position(100.0, 50.0);
+        assert_eq!(Hitbox::fit(shape, size, true).place(&still).axis, Vec2::X);
+    }
+}
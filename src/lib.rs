@@ -1,22 +1,336 @@
+pub mod atlas;
 pub mod background;
+pub mod behaviour;
+pub mod broad_phase;
 pub mod camera;
 pub mod character;
+pub mod clock;
 pub mod collision;
+pub mod control;
+pub mod damage;
+pub mod director;
 pub mod enemy;
+pub mod enemy_table;
+pub mod high_score;
+pub mod hitbox;
+pub mod loading;
+pub mod manifest;
+pub mod menu;
+pub mod pause_menu;
 pub mod player;
+pub mod pool;
+pub mod replay;
 pub mod restart_panel;
+pub mod rng;
 pub mod score_label;
+pub mod session;
+pub mod settings;
+pub mod sound;
+
+use {
+    crate::{
+        atlas::{build_atlases, SpriteAtlases},
+        background::{setup_background, BackgroundPlugin},
+        behaviour::{bounce_motion, dash_motion, homing_motion, wave_motion, EnemyBehaviour},
+        broad_phase::{build_spatial_hash, SpatialHash},
+        camera::{animate_camera, reset_camera, setup_camera, shake_camera},
+        character::{place_characters, RenderInterpolation},
+        clock::{simulation_tick, GameClockPlugin},
+        collision::{blink_player, check_collision, play_hit_sound, HitConfig},
+        control::ControlPlugin,
+        damage::GameMode,
+        director::{
+            direct_waves, reset_waves, WaveDirector, WaveTable, WaveTableHandle, WaveTableLoader,
+        },
+        enemy::{animate_enemy, move_enemy, setup_enemy, Enemy, EnemyKind},
+        enemy_table::{EnemyTable, EnemyTableHandle, EnemyTableLoader},
+        high_score::HighScorePlugin,
+        hitbox::{
+            attach_hitbox_outlines, show_hitbox_outlines, toggle_hitbox_overlay, HitboxOverlay,
+        },
+        loading::LoadingPlugin,
+        manifest::GameAssets,
+        menu::{open_menu, MenuPlugin},
+        pause_menu::PauseMenuPlugin,
+        player::{animate_player, move_player, setup_player},
+        pool::{recycle_enemies, EnemyPool},
+        replay::ReplayPlugin,
+        restart_panel::{
+            hide_restart_panel, perform_button_action, restart_panel_system, setup_restart_panel,
+            show_restart_panel, ButtonPressed,
+        },
+        rng::{report_seed, GameRng},
+        score_label::{update_score, ScorePlugin},
+        session::{advance_session, reset_session, GameSession},
+        settings::SettingsPlugin,
+        sound::SoundPlugin,
+    },
+    bevy::{asset::LoadState, prelude::*, transform::TransformSystem},
+};
 
 pub const Z_AXIS: f32 = 1.0;
+/// Sprites are drawn at this scale of the images
+pub const SPRITE_SCALE: f32 = 0.5;
+
+/// Movement and collision run in `SimulationStage` at this fixed rate (in seconds) of `GameClock`
+pub const SIMULATION_TICK: f64 = 1.0 / 60.0;
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, StageLabel)]
+pub struct SimulationStage;
 
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub enum AppState {
     Load,
+    /// an asset is missing; the error screen tells which
+    LoadFailed,
     Setup,
+    /// the title screen
+    Menu,
+    Options,
+    HighScores,
     Game,
+    /// pushed over `Game`, which freezes
+    Paused,
     Restart,
 }
 
-pub struct CollisionEvent;
+/// The systems that turn the player's input into `Character` movement
+#[derive(Debug, Clone, PartialEq, Eq, Hash, SystemLabel)]
+pub struct PlayerInput;
+
+/// The player was hit by an enemy of `kind` at `position`
+pub struct CollisionEvent {
+    pub kind: EnemyKind,
+    pub position: Vec2,
+}
 pub struct GameOverEvent;
 pub struct RestartEvent;
+/// A wave began; 1-based
+pub struct WaveEvent(pub usize);
+
+/// The playing field. It follows the primary window when there is one.
+#[derive(Debug, Resource)]
+pub struct Arena {
+    pub width: f32,
+    pub height: f32,
+}
+
+impl Default for Arena {
+    fn default() -> Self {
+        Self {
+            width: 1200.0,
+            height: 800.0,
+        }
+    }
+}
+
+//
+// Plugins
+//
+/// The game logic shared by the windowed and headless builds
+pub struct GameCorePlugin;
+
+impl Plugin for GameCorePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Arena>()
+            .init_resource::<GameRng>()
+            .init_resource::<RenderInterpolation>()
+            .add_asset::<EnemyTable>()
+            .init_asset_loader::<EnemyTableLoader>()
+            .init_resource::<EnemyTableHandle>()
+            .add_asset::<WaveTable>()
+            .init_asset_loader::<WaveTableLoader>()
+            .init_resource::<WaveTableHandle>()
+            .init_resource::<WaveDirector>()
+            .init_resource::<SpriteAtlases>()
+            .init_resource::<EnemyPool>()
+            .init_resource::<SpatialHash>()
+            .init_resource::<HitConfig>()
+            .init_resource::<GameMode>()
+            .init_resource::<GameSession>()
+            .init_resource::<GameAssets>()
+            .add_event::<CollisionEvent>()
+            .add_event::<GameOverEvent>()
+            .add_event::<RestartEvent>()
+            .add_event::<WaveEvent>()
+            .add_plugin(GameClockPlugin)
+            .add_plugin(ReplayPlugin)
+            .add_startup_system(report_seed)
+            .add_stage_after(
+                CoreStage::Update,
+                SimulationStage,
+                SystemStage::parallel().with_run_criteria(simulation_tick),
+            )
+            // `add_state` drives `AppState` in `CoreStage::Update` only
+            .add_system_set_to_stage(SimulationStage, State::<AppState>::get_driver())
+            .add_system_set(
+                SystemSet::on_enter(AppState::Setup)
+                    .with_system(build_atlases)
+                    .with_system(setup_player.after(build_atlases)),
+            )
+            // the order is fixed so that a replay reproduces the game
+            .add_system_set_to_stage(
+                SimulationStage,
+                SystemSet::on_update(AppState::Game)
+                    .with_system(move_player)
+                    .with_system(move_enemy)
+                    .with_system(
+                        homing_motion
+                            .label(EnemyBehaviour)
+                            .after(move_player)
+                            .after(move_enemy),
+                    )
+                    .with_system(wave_motion.label(EnemyBehaviour).after(homing_motion))
+                    .with_system(dash_motion.label(EnemyBehaviour).after(wave_motion))
+                    .with_system(bounce_motion.label(EnemyBehaviour).after(dash_motion))
+                    .with_system(build_spatial_hash.after(EnemyBehaviour))
+                    .with_system(check_collision.after(move_player).after(build_spatial_hash))
+                    .with_system(recycle_enemies.after(check_collision))
+                    .with_system(direct_waves.after(recycle_enemies))
+                    .with_system(setup_enemy.after(direct_waves))
+                    .with_system(advance_session.after(check_collision))
+                    .with_system(update_score.after(advance_session)),
+            )
+            // a new run, from the menu or a restart
+            .add_system_set(
+                SystemSet::on_enter(AppState::Game)
+                    .with_system(reset_session)
+                    .with_system(reset_waves),
+            )
+            .add_system_set(
+                SystemSet::on_update(AppState::Game)
+                    .with_system(animate_player)
+                    .with_system(animate_enemy)
+                    .with_system(game_over),
+            )
+            .add_system_set(SystemSet::on_exit(AppState::Game).with_system(clear_enemies))
+            .add_system_to_stage(
+                CoreStage::PostUpdate,
+                place_characters.before(TransformSystem::TransformPropagate),
+            )
+            .add_system(check_restart);
+    }
+}
+
+/// The full game; requires `DefaultPlugins`
+pub struct DodgePlugin;
+
+impl Plugin for DodgePlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(ClearColor(Color::rgb(0.6, 0.8, 1.0)))
+            .init_resource::<HitboxOverlay>()
+            .add_plugin(GameCorePlugin)
+            .add_plugin(LoadingPlugin)
+            .add_plugin(ControlPlugin)
+            .add_plugin(ScorePlugin)
+            .add_plugin(HighScorePlugin)
+            .add_plugin(PauseMenuPlugin)
+            .add_plugin(MenuPlugin)
+            .add_plugin(SettingsPlugin)
+            .add_plugin(SoundPlugin)
+            .add_event::<ButtonPressed>()
+            .add_plugin(BackgroundPlugin)
+            .add_state(AppState::Load)
+            .add_system_to_stage(CoreStage::PreUpdate, fit_arena_to_window)
+            .add_system(toggle_hitbox_overlay)
+            .add_system(attach_hitbox_outlines)
+            .add_system(show_hitbox_outlines.after(toggle_hitbox_overlay))
+            .add_system_set(
+                SystemSet::on_enter(AppState::Setup)
+                    .with_system(setup_background)
+                    .with_system(setup_camera)
+                    .with_system(setup_restart_panel)
+                    .with_system(open_menu),
+            )
+            .add_system_set(
+                SystemSet::on_enter(AppState::Game)
+                    .with_system(hide_restart_panel)
+                    .with_system(reset_camera),
+            )
+            .add_system_set(
+                SystemSet::on_update(AppState::Game)
+                    .with_system(shake_camera)
+                    .with_system(animate_camera)
+                    .with_system(blink_player)
+                    .with_system(play_hit_sound),
+            )
+            .add_system_set(SystemSet::on_enter(AppState::Restart).with_system(show_restart_panel))
+            // for the menus too
+            .add_system(restart_panel_system)
+            .add_system(perform_button_action.after(restart_panel_system));
+    }
+}
+
+/// The game without window, renderer nor audio; to be used with `MinimalPlugins`.
+/// It waits in `AppState::Setup` for the tables, which decide how a run starts.
+pub struct HeadlessPlugin;
+
+impl Plugin for HeadlessPlugin {
+    fn build(&self, app: &mut App) {
+        if !app.world.contains_resource::<AssetServer>() {
+            app.add_plugin(AssetPlugin::default());
+        }
+        app.add_asset::<Image>()
+            .add_asset::<TextureAtlas>()
+            .add_plugin(GameCorePlugin)
+            .add_state(AppState::Setup)
+            .add_system_set(SystemSet::on_update(AppState::Setup).with_system(start_with_tables));
+    }
+}
+
+//
+// Configuration
+//
+fn fit_arena_to_window(windows: Res<Windows>, mut arena: ResMut<Arena>) {
+    if let Some(window) = windows.get_primary() {
+        arena.width = window.width();
+        arena.height = window.height();
+    }
+}
+
+/// Once the tables are loaded, or failed to; the defaults stand in for a missing one
+fn start_with_tables(
+    asset_server: Res<AssetServer>,
+    enemy_table: Res<EnemyTableHandle>,
+    wave_table: Res<WaveTableHandle>,
+    mut state: ResMut<State<AppState>>,
+) {
+    let settled = |state| matches!(state, LoadState::Loaded | LoadState::Failed);
+    if settled(asset_server.get_load_state(&enemy_table.0))
+        && settled(asset_server.get_load_state(&wave_table.0))
+    {
+        state.set(AppState::Game).unwrap();
+    }
+}
+
+fn game_over(mut game_end: EventReader<GameOverEvent>, mut state: ResMut<State<AppState>>) {
+    // read them all, or another hit in the same frame would end the next game at once
+    if game_end.iter().count() > 0 {
+        state.set(AppState::Restart).unwrap();
+    }
+}
+
+/// At game over, or restarting from the pause menu
+fn clear_enemies(
+    mut commands: Commands,
+    mut enemies: Query<Entity, With<Enemy>>,
+    mut pool: ResMut<EnemyPool>,
+) {
+    for ent in enemies.iter_mut() {
+        commands.entity(ent).despawn_recursive();
+    }
+    pool.cleared();
+}
+
+/// From the restart panel or the pause menu. Entering `AppState::Game` resets the session.
+fn check_restart(
+    mut restart_channel: EventReader<RestartEvent>,
+    mut state: ResMut<State<AppState>>,
+) {
+    if restart_channel.iter().next().is_some()
+        && matches!(state.current(), AppState::Restart | AppState::Paused)
+    {
+        // unwinds `Paused` and `Game` under it
+        state.replace(AppState::Game).unwrap();
+    }
+}
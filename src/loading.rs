@@ -0,0 +1,343 @@
+use {
+    crate::{
+        director::WaveTableHandle,
+        enemy_table::{EnemyTable, EnemyTableHandle},
+        manifest::{
+            missing_names, AssetManifest, AssetManifestHandle, AssetManifestLoader, GameAssets,
+            BACKGROUND_SHADER, UI_FONT,
+        },
+        player::PLAYER_SPRITES,
+        sound::{Track, SOUND_EFFECTS},
+        AppState,
+    },
+    bevy::{asset::LoadState, prelude::*, utils::Instant},
+};
+
+//
+// Loading screen: progress by group, and what failed when it does
+//
+/// Give up on what is still loading after this many seconds
+const LOAD_TIMEOUT: f32 = 30.0;
+
+/// Assets loaded together and shown as one progress bar
+#[derive(Debug)]
+pub struct AssetGroup {
+    pub name: &'static str,
+    pub handles: Vec<HandleUntyped>,
+    /// a failure here isn't fatal, as placeholders stand in for sprites and sounds
+    pub optional: bool,
+}
+
+/// Every asset the game waits for before `AppState::Setup`
+#[derive(Debug, Default, Resource)]
+pub struct GameResourceHandles {
+    pub groups: Vec<AssetGroup>,
+    /// the assets that failed or timed out, and names missing from the asset manifest
+    pub failures: Vec<String>,
+    /// on the wall clock
+    started: Option<Instant>,
+}
+
+/// The root of the loading screen
+#[derive(Component, Debug)]
+struct LoadingScreen;
+
+/// The filled part of the bar for the group at this index
+#[derive(Component, Debug)]
+struct LoadingBar(usize);
+
+/// "sprites 3/9" for the group at this index
+#[derive(Component, Debug)]
+struct LoadingLabel(usize);
+
+/// The list of failures on the error screen
+#[derive(Component, Debug)]
+struct LoadErrorScreen;
+
+/// The systems for `DodgePlugin`, from `AppState::Load` to `AppState::Setup` or `AppState::LoadFailed`
+pub struct LoadingPlugin;
+
+impl Plugin for LoadingPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_asset::<AssetManifest>()
+            .init_asset_loader::<AssetManifestLoader>()
+            .init_resource::<AssetManifestHandle>()
+            .init_resource::<GameResourceHandles>()
+            .add_system_set(SystemSet::on_enter(AppState::Load).with_system(load_manifest))
+            .add_system_set(
+                SystemSet::on_update(AppState::Load)
+                    .with_system(load_assets)
+                    .with_system(check_assets.after(load_assets)),
+            )
+            .add_system_set(SystemSet::on_exit(AppState::Load).with_system(close_loading_screen))
+            .add_system_set(
+                SystemSet::on_enter(AppState::LoadFailed).with_system(show_load_errors),
+            );
+    }
+}
+
+/// Wait for the manifest first, as it lists the rest, and the tables along with it
+fn load_manifest(
+    mut handles: ResMut<GameResourceHandles>,
+    manifest: Res<AssetManifestHandle>,
+    enemy_table: Res<EnemyTableHandle>,
+    wave_table: Res<WaveTableHandle>,
+) {
+    handles.started = Some(Instant::now());
+    handles.groups = vec![
+        AssetGroup {
+            name: "manifest",
+            handles: vec![manifest.0.clone_untyped()],
+            optional: false,
+        },
+        AssetGroup {
+            name: "tables",
+            handles: vec![enemy_table.0.clone_untyped(), wave_table.0.clone_untyped()],
+            optional: false,
+        },
+    ];
+}
+
+/// Once the manifest is read, wait for everything in it too, and check that it has
+/// every name the code uses
+fn load_assets(
+    mut commands: Commands,
+    mut handles: ResMut<GameResourceHandles>,
+    mut assets: ResMut<GameAssets>,
+    asset_server: Res<AssetServer>,
+    manifest: Res<AssetManifestHandle>,
+    manifests: Res<Assets<AssetManifest>>,
+    mut done: Local<bool>,
+) {
+    let manifest = match manifests.get(&manifest.0) {
+        Some(manifest) if !*done => manifest,
+        _ => return,
+    };
+    *done = true;
+    *assets = GameAssets::load(manifest, &asset_server);
+    let failures = [
+        missing_names("sprite", &assets.sprites, PLAYER_SPRITES),
+        missing_names(
+            "sound",
+            &assets.sounds,
+            Track::ALL
+                .iter()
+                .map(|track| track.name())
+                .chain(SOUND_EFFECTS),
+        ),
+        missing_names("font", &assets.fonts, [UI_FONT]),
+        missing_names("shader", &assets.shaders, [BACKGROUND_SHADER]),
+    ]
+    .concat();
+    handles.failures.extend(failures);
+    handles.groups.extend([
+        AssetGroup {
+            name: "sprites",
+            handles: assets.sprites.values().map(Handle::clone_untyped).collect(),
+            optional: true,
+        },
+        AssetGroup {
+            name: "sounds",
+            handles: assets.sounds.values().map(Handle::clone_untyped).collect(),
+            optional: true,
+        },
+        AssetGroup {
+            name: "fonts",
+            handles: assets.fonts.values().map(Handle::clone_untyped).collect(),
+            optional: false,
+        },
+        AssetGroup {
+            name: "shaders",
+            handles: assets.shaders.values().map(Handle::clone_untyped).collect(),
+            optional: false,
+        },
+    ]);
+    setup_loading_screen(&mut commands, &assets, &handles);
+}
+
+fn setup_loading_screen(
+    commands: &mut Commands,
+    assets: &GameAssets,
+    handles: &GameResourceHandles,
+) {
+    // shown once the font itself is loaded; the bars don't need it
+    let font = assets.font(UI_FONT);
+    commands
+        .spawn(NodeBundle {
+            style: Style {
+                size: Size::new(Val::Percent(100.0), Val::Percent(100.0)),
+                flex_direction: FlexDirection::Column,
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                ..Default::default()
+            },
+            ..Default::default()
+        })
+        .insert(LoadingScreen)
+        .with_children(|parent| {
+            for (i, group) in handles.groups.iter().enumerate() {
+                parent
+                    .spawn(TextBundle {
+                        text: Text::from_section(
+                            group.name,
+                            TextStyle {
+                                font: font.clone(),
+                                font_size: 24.0,
+                                color: Color::WHITE,
+                            },
+                        ),
+                        ..Default::default()
+                    })
+                    .insert(LoadingLabel(i));
+                parent
+                    .spawn(NodeBundle {
+                        style: Style {
+                            size: Size::new(Val::Px(400.0), Val::Px(16.0)),
+                            margin: UiRect::vertical(Val::Px(8.0)),
+                            padding: UiRect::all(Val::Px(2.0)),
+                            ..Default::default()
+                        },
+                        background_color: Color::rgba(0.0, 0.0, 0.0, 0.5).into(),
+                        ..Default::default()
+                    })
+                    .with_children(|bar| {
+                        bar.spawn(NodeBundle {
+                            style: Style {
+                                size: Size::new(Val::Percent(0.0), Val::Percent(100.0)),
+                                ..Default::default()
+                            },
+                            background_color: Color::GOLD.into(),
+                            ..Default::default()
+                        })
+                        .insert(LoadingBar(i));
+                    });
+            }
+        });
+}
+
+/// Go on when everything is loaded; stop at the first failure or at the timeout
+#[allow(clippy::too_many_arguments)]
+fn check_assets(
+    mut state: ResMut<State<AppState>>,
+    mut handles: ResMut<GameResourceHandles>,
+    asset_server: Res<AssetServer>,
+    assets: Res<GameAssets>,
+    enemy_table: Res<EnemyTableHandle>,
+    enemy_tables: Res<Assets<EnemyTable>>,
+    mut bars: Query<(&mut Style, &LoadingBar)>,
+    mut labels: Query<(&mut Text, &LoadingLabel)>,
+) {
+    let path_of = |handle: &HandleUntyped| {
+        asset_server.get_handle_path(handle).map_or_else(
+            || format!("{:?}", handle.id),
+            |path| path.path().display().to_string(),
+        )
+    };
+    let mut failed = Vec::new();
+    let mut substituted = Vec::new();
+    let mut pending = Vec::new();
+    let mut progress = Vec::new();
+    for group in handles.groups.iter() {
+        let mut loaded = 0;
+        for handle in group.handles.iter() {
+            match asset_server.get_load_state(handle) {
+                LoadState::Loaded => loaded += 1,
+                LoadState::Failed if group.optional => {
+                    loaded += 1;
+                    substituted.push(path_of(handle));
+                }
+                LoadState::Failed => failed.push(path_of(handle)),
+                _ => pending.push(path_of(handle)),
+            }
+        }
+        progress.push((loaded, group.handles.len()));
+    }
+    for (mut style, LoadingBar(i)) in bars.iter_mut() {
+        let (loaded, total) = progress[*i];
+        style.size.width = Val::Percent(100.0 * loaded as f32 / total.max(1) as f32);
+    }
+    for (mut text, LoadingLabel(i)) in labels.iter_mut() {
+        let (loaded, total) = progress[*i];
+        text.sections[0].value = format!("{} {}/{}", handles.groups[*i].name, loaded, total);
+    }
+
+    let timed_out = handles
+        .started
+        .is_some_and(|started| LOAD_TIMEOUT < started.elapsed().as_secs_f32());
+    if pending.is_empty() {
+        // the table names its sprites, so they can only be checked now;
+        // like a sprite that fails to load, an unknown one gets a placeholder
+        if let Some(table) = enemy_tables.get(&enemy_table.0) {
+            substituted.extend(missing_names(
+                "sprite",
+                &assets.sprites,
+                table
+                    .kinds
+                    .iter()
+                    .flat_map(|def| def.sprites.iter().map(String::as_str)),
+            ));
+        }
+    }
+    if !failed.is_empty() || !handles.failures.is_empty() {
+        handles.failures.extend(failed);
+    } else if pending.is_empty() {
+        for path in substituted.iter() {
+            warn!("failed to load {}; a placeholder will stand in", path);
+        }
+        state.set(AppState::Setup).unwrap();
+        return;
+    } else if timed_out {
+        handles.failures.extend(
+            pending
+                .into_iter()
+                .map(|path| format!("{} (timed out)", path)),
+        );
+    } else {
+        return;
+    }
+    for failure in handles.failures.iter() {
+        error!("failed to load {}", failure);
+    }
+    state.set(AppState::LoadFailed).unwrap();
+}
+
+fn close_loading_screen(mut commands: Commands, query: Query<Entity, With<LoadingScreen>>) {
+    for entity in query.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+/// It can't be read if the font is the one missing, but the log has the same
+fn show_load_errors(
+    mut commands: Commands,
+    assets: Res<GameAssets>,
+    handles: Res<GameResourceHandles>,
+) {
+    let mut message = String::from("Failed to load:\n");
+    for failure in handles.failures.iter() {
+        message.push_str(&format!("  {}\n", failure));
+    }
+    message.push_str("\nCheck the assets folder and restart the game.");
+    commands
+        .spawn(TextBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                position: UiRect {
+                    left: Val::Percent(5.0),
+                    top: Val::Percent(10.0),
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+            text: Text::from_section(
+                message,
+                TextStyle {
+                    font: assets.font(UI_FONT),
+                    font_size: 28.0,
+                    color: Color::rgb(1.0, 0.3, 0.3),
+                },
+            ),
+            ..Default::default()
+        })
+        .insert(LoadErrorScreen);
+}
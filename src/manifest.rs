@@ -0,0 +1,119 @@
+use {
+    bevy::{
+        asset::{Asset, AssetLoader, BoxedFuture, Error, LoadContext, LoadedAsset},
+        prelude::*,
+        reflect::TypeUuid,
+        utils::HashMap,
+    },
+    serde::Deserialize,
+    std::collections::BTreeMap,
+};
+
+//
+// Asset manifest: every asset by name, listed in `assets/game.manifest.ron`
+//
+pub const MANIFEST_PATH: &str = "game.manifest.ron";
+/// The font of every label
+pub const UI_FONT: &str = "ui";
+pub const BACKGROUND_SHADER: &str = "background";
+
+/// Logical names to paths under `assets/`
+#[derive(Debug, Default, Deserialize, TypeUuid)]
+#[uuid = "4d22922f-51c0-4da9-bc73-f4b22b2e6f36"]
+pub struct AssetManifest {
+    #[serde(default)]
+    pub sprites: BTreeMap<String, String>,
+    #[serde(default)]
+    pub sounds: BTreeMap<String, String>,
+    #[serde(default)]
+    pub fonts: BTreeMap<String, String>,
+    #[serde(default)]
+    pub shaders: BTreeMap<String, String>,
+}
+
+#[derive(Debug, Resource)]
+pub struct AssetManifestHandle(pub Handle<AssetManifest>);
+
+impl FromWorld for AssetManifestHandle {
+    fn from_world(world: &mut World) -> Self {
+        let asset_server = world.resource::<AssetServer>();
+        AssetManifestHandle(asset_server.load(MANIFEST_PATH))
+    }
+}
+
+#[derive(Default)]
+pub struct AssetManifestLoader;
+
+impl AssetLoader for AssetManifestLoader {
+    fn load<'a>(
+        &'a self,
+        bytes: &'a [u8],
+        load_context: &'a mut LoadContext,
+    ) -> BoxedFuture<'a, Result<(), Error>> {
+        Box::pin(async move {
+            let manifest: AssetManifest = ron::de::from_bytes(bytes)?;
+            load_context.set_default_asset(LoadedAsset::new(manifest));
+            Ok(())
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["manifest.ron"]
+    }
+}
+
+/// The handles of the assets in an `AssetManifest`, by name. `LoadingPlugin` fills it
+/// once the manifest is read; it stays empty in headless runs, where placeholders stand in for every sprite.
+#[derive(Debug, Default, Resource)]
+pub struct GameAssets {
+    pub sprites: HashMap<String, Handle<Image>>,
+    pub sounds: HashMap<String, Handle<AudioSource>>,
+    pub fonts: HashMap<String, Handle<Font>>,
+    pub shaders: HashMap<String, Handle<Shader>>,
+}
+
+fn load_all<T: Asset>(
+    paths: &BTreeMap<String, String>,
+    asset_server: &AssetServer,
+) -> HashMap<String, Handle<T>> {
+    paths
+        .iter()
+        .map(|(name, path)| (name.clone(), asset_server.load(path.as_str())))
+        .collect()
+}
+
+impl GameAssets {
+    /// start loading everything in `manifest`
+    pub fn load(manifest: &AssetManifest, asset_server: &AssetServer) -> Self {
+        GameAssets {
+            sprites: load_all(&manifest.sprites, asset_server),
+            sounds: load_all(&manifest.sounds, asset_server),
+            fonts: load_all(&manifest.fonts, asset_server),
+            shaders: load_all(&manifest.shaders, asset_server),
+        }
+    }
+    pub fn sprite(&self, name: &str) -> Option<&Handle<Image>> {
+        self.sprites.get(name)
+    }
+    pub fn sound(&self, name: &str) -> Option<&Handle<AudioSource>> {
+        self.sounds.get(name)
+    }
+    /// `LoadingPlugin` checks that the names the game uses are there,
+    /// so the default handle only shows up in headless runs
+    pub fn font(&self, name: &str) -> Handle<Font> {
+        self.fonts.get(name).cloned().unwrap_or_default()
+    }
+}
+
+/// Those of `names` without a handle, described as `kind`s for the error screen
+pub fn missing_names<'a, T: Asset>(
+    kind: &str,
+    handles: &HashMap<String, Handle<T>>,
+    names: impl IntoIterator<Item = &'a str>,
+) -> Vec<String> {
+    names
+        .into_iter()
+        .filter(|name| !handles.contains_key(*name))
+        .map(|name| format!("{} \"{}\" (not in the manifest)", kind, name))
+        .collect()
+}
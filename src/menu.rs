@@ -0,0 +1,209 @@
+use {
+    crate::{
+        high_score::NameEntry,
+        manifest::{GameAssets, UI_FONT},
+        player::Player,
+        replay::ReplayPlayer,
+        restart_panel::{
+            spawn_button, ButtonAction, ButtonPressed, GameButton, HOVERED_BUTTON, NORMAL_BUTTON,
+        },
+        AppState,
+    },
+    bevy::prelude::*,
+};
+
+//
+// Title screen and main menu
+//
+/// Shown only in the given state
+#[derive(Component, Debug)]
+pub struct Screen(pub AppState);
+
+/// The button chosen with the keyboard or a gamepad
+#[derive(Debug, Default, Resource)]
+pub struct ButtonFocus(pub Option<Entity>);
+
+/// The systems for `DodgePlugin`
+pub struct MenuPlugin;
+
+impl Plugin for MenuPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<ButtonFocus>()
+            .add_system_set(SystemSet::on_enter(AppState::Setup).with_system(setup_menu))
+            .add_system(show_screens)
+            .add_system(navigate_buttons)
+            .add_system_set(SystemSet::on_enter(AppState::Menu).with_system(hide_player))
+            .add_system_set(SystemSet::on_enter(AppState::Game).with_system(show_player));
+    }
+}
+
+/// Leave `AppState::Setup` for the menu, or straight for the game when replaying
+pub fn open_menu(mut state: ResMut<State<AppState>>, replay_player: Option<Res<ReplayPlayer>>) {
+    if replay_player.is_some() {
+        state.set(AppState::Game).unwrap();
+    } else {
+        state.set(AppState::Menu).unwrap();
+    }
+}
+
+fn title(font: Handle<Font>, value: &str) -> TextBundle {
+    TextBundle {
+        style: Style {
+            display: Display::None,
+            position_type: PositionType::Absolute,
+            position: UiRect {
+                left: Val::Percent(36.0),
+                top: Val::Percent(12.0),
+                ..Default::default()
+            },
+            ..Default::default()
+        },
+        text: Text::from_section(
+            value,
+            TextStyle {
+                font,
+                font_size: 100.0,
+                color: Color::WHITE,
+            },
+        ),
+        ..Default::default()
+    }
+}
+
+fn setup_menu(mut commands: Commands, assets: Res<GameAssets>) {
+    let font = assets.font(UI_FONT);
+    let color = Color::rgb(0.6, 0.9, 0.8);
+    commands
+        .spawn(title(font.clone(), "Dodge!"))
+        .insert(Screen(AppState::Menu));
+    for (label, action) in [
+        ("Start", ButtonAction::Start),
+        ("Options", ButtonAction::Options),
+        ("High Scores", ButtonAction::HighScores),
+        ("Quit", ButtonAction::Exit),
+    ] {
+        let color = if action == ButtonAction::Exit {
+            Color::rgb(1.0, 0.5, 0.5)
+        } else {
+            color
+        };
+        spawn_button(&mut commands, font.clone(), label, color, action)
+            .insert(Screen(AppState::Menu));
+    }
+    for (label, state) in [
+        ("Options", AppState::Options),
+        ("High Scores", AppState::HighScores),
+    ] {
+        commands
+            .spawn(title(font.clone(), label))
+            .insert(Screen(state));
+    }
+    // the options screen lays out its own, under the settings
+    spawn_button(
+        &mut commands,
+        font.clone(),
+        "Back",
+        color,
+        ButtonAction::Back,
+    )
+    .insert(Screen(AppState::HighScores));
+}
+
+fn show_screens(state: Res<State<AppState>>, mut query: Query<(&mut Style, &Screen)>) {
+    if !state.is_changed() {
+        return;
+    }
+    for (mut style, Screen(shown_in)) in query.iter_mut() {
+        style.display = if shown_in == state.current() {
+            Display::Flex
+        } else {
+            Display::None
+        };
+    }
+}
+
+fn hide_player(mut query: Query<&mut Visibility, With<Player>>) {
+    for mut visibility in query.iter_mut() {
+        visibility.is_visible = false;
+    }
+}
+
+fn show_player(mut query: Query<&mut Visibility, With<Player>>) {
+    for mut visibility in query.iter_mut() {
+        visibility.is_visible = true;
+    }
+}
+
+/// Up and down (or W and S, or the D-pad) move among the shown buttons, and
+/// `Return` (or the south button) presses one. The menu starts at its first button.
+/// Nothing moves while a name is typed for a new record, nor on the frame it is committed.
+#[allow(clippy::too_many_arguments, clippy::type_complexity)]
+fn navigate_buttons(
+    state: Res<State<AppState>>,
+    name_entry: Res<NameEntry>,
+    mut was_typing: Local<bool>,
+    keys: Res<Input<KeyCode>>,
+    gamepads: Res<Gamepads>,
+    pad_buttons: Res<Input<GamepadButton>>,
+    mut focus: ResMut<ButtonFocus>,
+    mut pressed_events: EventWriter<ButtonPressed>,
+    hovered: Query<(Entity, &Interaction), (Changed<Interaction>, With<GameButton>)>,
+    mut buttons: Query<(Entity, &GameButton, &Style, &mut BackgroundColor)>,
+) {
+    let typing = name_entry.pending.is_some();
+    if typing || *was_typing {
+        *was_typing = typing;
+        return;
+    }
+    let mut shown = buttons
+        .iter()
+        .filter(|(_, _, style, _)| style.display == Display::Flex)
+        .map(|(entity, ..)| entity)
+        .collect::<Vec<Entity>>();
+    shown.sort_unstable();
+    let pad = |button_type| {
+        gamepads
+            .iter()
+            .any(|gamepad| pad_buttons.just_pressed(GamepadButton::new(gamepad, button_type)))
+    };
+    let up = keys.any_just_pressed([KeyCode::Up, KeyCode::W]) || pad(GamepadButtonType::DPadUp);
+    let down =
+        keys.any_just_pressed([KeyCode::Down, KeyCode::S]) || pad(GamepadButtonType::DPadDown);
+    let activate = keys.just_pressed(KeyCode::Return) || pad(GamepadButtonType::South);
+
+    let mut current = focus
+        .0
+        .and_then(|entity| shown.iter().position(|e| *e == entity));
+    if current.is_none() && *state.current() == AppState::Menu && !shown.is_empty() {
+        current = Some(0);
+    }
+    for (entity, interaction) in hovered.iter() {
+        if *interaction == Interaction::Hovered {
+            current = shown.iter().position(|e| *e == entity).or(current);
+        }
+    }
+    if !shown.is_empty() {
+        let n = shown.len();
+        if up {
+            current = Some(current.map_or(n - 1, |i| (i + n - 1) % n));
+        } else if down {
+            current = Some(current.map_or(0, |i| (i + 1) % n));
+        }
+    }
+    let next = current.map(|i| shown[i]);
+    if focus.0 != next {
+        focus.0 = next;
+        for (entity, _, _, mut color) in buttons.iter_mut() {
+            *color = if Some(entity) == next {
+                HOVERED_BUTTON.into()
+            } else {
+                NORMAL_BUTTON.into()
+            };
+        }
+    }
+    if activate {
+        if let Some((_, button, _, _)) = next.and_then(|entity| buttons.get(entity).ok()) {
+            pressed_events.send(ButtonPressed(button.action));
+        }
+    }
+}
@@ -0,0 +1,109 @@
+use {
+    crate::{
+        manifest::{GameAssets, UI_FONT},
+        restart_panel::{spawn_button, ButtonAction},
+        AppState,
+    },
+    bevy::{prelude::*, window::WindowFocused},
+};
+
+//
+// Pause menu
+//
+/// The label and the buttons of the pause menu
+#[derive(Component, Debug, Default)]
+pub struct PauseMenu;
+
+/// The systems for `DodgePlugin`. `Esc` or `P` pauses and resumes; losing focus pauses.
+/// `Time` stands still while paused, and so do `GameClock` and the animations.
+pub struct PauseMenuPlugin;
+
+impl Plugin for PauseMenuPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system_set(SystemSet::on_enter(AppState::Setup).with_system(setup_pause_menu))
+            .add_system(toggle_pause)
+            .add_system_set(
+                SystemSet::on_enter(AppState::Paused)
+                    .with_system(show_pause_menu)
+                    .with_system(stop_time),
+            )
+            .add_system_set(
+                SystemSet::on_exit(AppState::Paused)
+                    .with_system(hide_pause_menu)
+                    .with_system(resume_time),
+            );
+    }
+}
+
+fn toggle_pause(
+    keys: Res<Input<KeyCode>>,
+    mut focus_events: EventReader<WindowFocused>,
+    mut state: ResMut<State<AppState>>,
+) {
+    let pressed = keys.any_just_pressed([KeyCode::Escape, KeyCode::P]);
+    let lost_focus = focus_events.iter().any(|event| !event.focused);
+    // they fail only when another transition is queued, e.g. at game over
+    match state.current() {
+        AppState::Game if pressed || lost_focus => {
+            let _ = state.push(AppState::Paused);
+        }
+        AppState::Paused if pressed => {
+            let _ = state.pop();
+        }
+        _ => (),
+    }
+}
+
+fn setup_pause_menu(mut commands: Commands, assets: Res<GameAssets>) {
+    let font = assets.font(UI_FONT);
+    commands
+        .spawn(TextBundle {
+            style: Style {
+                display: Display::None,
+                position_type: PositionType::Absolute,
+                position: UiRect {
+                    left: Val::Percent(42.0),
+                    top: Val::Percent(20.0),
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+            text: Text::from_section(
+                "Paused",
+                TextStyle {
+                    font: font.clone(),
+                    font_size: 60.0,
+                    color: Color::WHITE,
+                },
+            ),
+            ..Default::default()
+        })
+        .insert(PauseMenu);
+    for (label, color, action) in [
+        ("Resume", Color::rgb(0.6, 0.9, 0.8), ButtonAction::Resume),
+        ("Restart", Color::rgb(0.6, 0.9, 0.8), ButtonAction::Restart),
+        ("Quit", Color::rgb(1.0, 0.5, 0.5), ButtonAction::Exit),
+    ] {
+        spawn_button(&mut commands, font.clone(), label, color, action).insert(PauseMenu);
+    }
+}
+
+fn show_pause_menu(mut query: Query<&mut Style, With<PauseMenu>>) {
+    for mut style in query.iter_mut() {
+        style.display = Display::Flex;
+    }
+}
+
+fn hide_pause_menu(mut query: Query<&mut Style, With<PauseMenu>>) {
+    for mut style in query.iter_mut() {
+        style.display = Display::None;
+    }
+}
+
+fn stop_time(mut time: ResMut<Time>) {
+    time.pause();
+}
+
+fn resume_time(mut time: ResMut<Time>) {
+    time.unpause();
+}
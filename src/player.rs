@@ -1,7 +1,12 @@
 use {
     crate::{
+        atlas::SpriteAtlases,
         character::{Character, SpawnTimer},
-        Z_AXIS,
+        clock::GameClock,
+        collision::{HitConfig, HitRecovery},
+        damage::Vitality,
+        hitbox::{Hitbox, Shape},
+        Arena, SIMULATION_TICK, SPRITE_SCALE, Z_AXIS,
     },
     bevy::prelude::*,
 };
@@ -9,77 +14,65 @@ use {
 //
 // Player
 //
+/// The score and the rest of a run are in `GameSession`
 #[derive(Component, Debug, Default)]
-pub struct Player {
-    pub score: f32,
-    pub max_score: f32,
-}
+pub struct Player;
 
-pub fn setup_player(
-    mut commands: Commands,
-    asset_server: Res<AssetServer>,
-    mut texture_atlases: ResMut<Assets<TextureAtlas>>,
-    mut textures: ResMut<Assets<Image>>,
-) {
-    let mut texture_atlas_builder = TextureAtlasBuilder::default();
-    let sprite_handles = [
-        asset_server.get_handle("sprites/bevy_logo_dark_1.png"),
-        asset_server.get_handle("sprites/bevy_logo_dark_2.png"),
-        asset_server.get_handle("sprites/bevy_logo_dark_3.png"),
-    ];
-    for handle in sprite_handles.iter() {
-        if let Some(image) = textures.get(handle) {
-            texture_atlas_builder.add_texture(handle.clone_weak(), image);
-        }
-    }
-    let texture_atlas = texture_atlas_builder.finish(&mut textures).unwrap();
-    let vendor_index = texture_atlas.get_texture_index(&sprite_handles[0]).unwrap();
-    let atlas_handle = texture_atlases.add(texture_atlas.clone());
+/// The frames, by their names in the asset manifest
+pub const PLAYER_SPRITES: [&str; 3] = ["player_1", "player_2", "player_3"];
+
+/// In fractions of the first sprite, which is a bit more forgiving than its look
+pub const PLAYER_HITBOX: Shape = Shape::Circle { radius: 0.3 };
 
+pub fn setup_player(mut commands: Commands, atlases: Res<SpriteAtlases>) {
+    let atlas = &atlases.player;
     commands
         .spawn(SpriteSheetBundle {
             transform: Transform {
                 translation: Vec3::new(0.0, 0.0, Z_AXIS),
-                scale: Vec3::splat(0.5),
+                scale: Vec3::splat(SPRITE_SCALE),
                 ..Default::default()
             },
-            sprite: TextureAtlasSprite::new(vendor_index),
-            texture_atlas: atlas_handle,
+            sprite: TextureAtlasSprite::new(atlas.first),
+            texture_atlas: atlas.handle.clone(),
             ..Default::default()
         })
         .insert(SpawnTimer(Timer::from_seconds(0.15, TimerMode::Repeating)))
-        .insert(Character::from(texture_atlas))
-        .insert(Player::default());
+        .insert(Character::from(atlas))
+        .insert(Hitbox::fit(PLAYER_HITBOX, atlas.size, false))
+        .insert(HitRecovery::default())
+        .insert(Vitality::default())
+        .insert(Player);
+}
+
+/// in pixels per second
+pub const PLAYER_SPEED: f32 = 600.0;
+
+pub fn move_player(
+    arena: Res<Arena>,
+    config: Res<HitConfig>,
+    mut query: Query<(&mut Character, &mut HitRecovery), With<Player>>,
+) {
+    let win_width = arena.width;
+    let win_height = arena.height;
+    for (mut player, mut recovery) in query.iter_mut() {
+        player.step(SIMULATION_TICK as f32);
+        recovery.step(&mut player, &config, SIMULATION_TICK as f32);
+        player.trans_x = player.trans_x.clamp(-0.45 * win_width, 0.45 * win_width);
+        player.trans_y = player.trans_y.clamp(-0.45 * win_height, 0.45 * win_height);
+    }
 }
 
 // (from 'sprite_sheet')
 #[allow(clippy::type_complexity)]
 pub fn animate_player(
-    windows: Res<Windows>,
-    time: Res<Time>,
-    mut query: Query<
-        (
-            &mut Character,
-            &mut SpawnTimer,
-            &mut Transform,
-            &mut TextureAtlasSprite,
-        ),
-        With<Player>,
-    >,
+    clock: Res<GameClock>,
+    mut query: Query<(&Character, &mut SpawnTimer, &mut TextureAtlasSprite), With<Player>>,
 ) {
-    let window = windows.get_primary().unwrap();
-    let win_width = window.width();
-    let win_height = window.height();
-    for (mut player, mut timer, mut trans, mut sprite) in query.iter_mut() {
-        trans.translation.x =
-            (trans.translation.x + player.diff_x).clamp(-0.45 * win_width, 0.45 * win_width);
-        trans.translation.y =
-            (trans.translation.y + player.diff_y).clamp(-0.45 * win_height, 0.45 * win_height);
-        player.trans_x = trans.translation.x;
-        player.trans_y = trans.translation.y;
-        timer.tick(time.delta());
+    for (player, mut timer, mut sprite) in query.iter_mut() {
+        timer.tick(clock.delta());
         if timer.finished() {
-            sprite.index = (sprite.index + 1) % player.texture_atlas.textures.len();
+            sprite.index = (sprite.index + 1) % player.frames.max(1);
             sprite.flip_x = player.flip;
         }
     }
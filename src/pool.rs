@@ -0,0 +1,201 @@
+use {
+    crate::{
+        character::Character,
+        director::WaveDirector,
+        enemy::{entry_point, Enemy},
+        enemy_table::{EnemyTable, EnemyTableHandle},
+        hitbox::Hitbox,
+        rng::GameRng,
+        Arena,
+    },
+    bevy::prelude::*,
+};
+
+//
+// Enemy pool: what to do with the enemies that left the arena
+//
+/// Counters, updated every simulation tick
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct PoolStats {
+    /// enemies alive now
+    pub live: usize,
+    /// the most enemies alive at once
+    pub peak: usize,
+    /// spawned as new entities
+    pub spawned: usize,
+    /// sent back into the arena after leaving it
+    pub recycled: usize,
+    /// removed after leaving the arena
+    pub despawned: usize,
+}
+
+#[derive(Debug, Resource)]
+pub struct EnemyPool {
+    /// no more enemies than this at once, whatever the wave says
+    pub capacity: usize,
+    stats: PoolStats,
+}
+
+impl Default for EnemyPool {
+    fn default() -> Self {
+        Self {
+            capacity: 256,
+            stats: PoolStats::default(),
+        }
+    }
+}
+
+impl EnemyPool {
+    pub fn stats(&self) -> PoolStats {
+        self.stats
+    }
+
+    /// another enemy can be spawned
+    pub fn has_room(&self) -> bool {
+        self.stats.live < self.capacity
+    }
+
+    /// count an enemy spawned by `setup_enemy`
+    pub fn spawned(&mut self) {
+        self.stats.spawned += 1;
+        self.stats.live += 1;
+        self.stats.peak = self.stats.peak.max(self.stats.live);
+    }
+
+    /// count all the enemies despawned at once, e.g. at game over
+    pub fn cleared(&mut self) {
+        self.stats.despawned += self.stats.live;
+        self.stats.live = 0;
+    }
+}
+
+/// An enemy has left when its hitbox is completely out on either axis
+fn has_left(arena: &Arena, enemy: &Character, hitbox: &Hitbox) -> bool {
+    let margin = hitbox.shape.reach();
+    0.5 * arena.width + margin < enemy.trans_x.abs()
+        || 0.5 * arena.height + margin < enemy.trans_y.abs()
+}
+
+/// Recycle the enemies that left the arena, or despawn them when there are more than
+/// the current wave allows, or the wave doesn't send their kind any more
+#[allow(clippy::too_many_arguments)]
+pub fn recycle_enemies(
+    mut commands: Commands,
+    arena: Res<Arena>,
+    director: Res<WaveDirector>,
+    table_handle: Res<EnemyTableHandle>,
+    tables: Res<Assets<EnemyTable>>,
+    mut pool: ResMut<EnemyPool>,
+    mut rng: ResMut<GameRng>,
+    mut query: Query<(Entity, &mut Character, &mut Enemy, &Hitbox)>,
+) {
+    let table = tables.get(&table_handle.0);
+    let limit = pool.capacity.min(director.current.max_enemies);
+    let mut live = query.iter().count();
+    for (entity, mut enemy, mut et, hitbox) in query.iter_mut() {
+        if !has_left(&arena, &enemy, hitbox) {
+            continue;
+        }
+        let wanted = table
+            .and_then(|table| table.kinds.get(et.kind.0))
+            .is_none_or(|def| 0.0 < director.weight(&def.name, def.spawn_weight));
+        if limit < live || !wanted {
+            commands.entity(entity).despawn_recursive();
+            live -= 1;
+            pool.stats.despawned += 1;
+            continue;
+        }
+        let (px, py, mut dx, mut dy) = entry_point(&mut rng, &arena);
+        let dist: f32 = (dx.powi(2) + dy.powi(2)).sqrt();
+        dx *= et.speed / dist;
+        dy *= et.speed / dist;
+        enemy.warp_to(px, py);
+        enemy.diff_x = dx;
+        enemy.diff_y = dy;
+        et.collided = false;
+        pool.stats.recycled += 1;
+    }
+    pool.stats.live = live;
+    pool.stats.peak = pool.stats.peak.max(live);
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        crate::{atlas::SpriteAtlas, enemy::EnemyKind, hitbox::Shape},
+    };
+
+    fn spawn_enemy(app: &mut App, x: f32) {
+        app.world.spawn((
+            Character::from(&SpriteAtlas::default()).with_position(x, 0.0),
+            Enemy {
+                kind: EnemyKind(0),
+                collided: true,
+                speed: 100.0,
+                acceleration: 1.0,
+                damage: 25.0,
+            },
+            Hitbox {
+                shape: Shape::Circle { radius: 10.0 },
+                rotates: false,
+            },
+        ));
+        app.world.resource_mut::<EnemyPool>().spawned();
+    }
+
+    #[test]
+    fn enemies_past_capacity_are_despawned_and_the_rest_recycled() {
+        let mut app = App::new();
+        app.add_plugins(MinimalPlugins)
+            .add_plugin(AssetPlugin::default())
+            .add_asset::<EnemyTable>()
+            .init_resource::<Arena>()
+            .init_resource::<WaveDirector>()
+            .insert_resource(EnemyTableHandle(Handle::default()))
+            .insert_resource(GameRng::new(1))
+            .insert_resource(EnemyPool {
+                capacity: 4,
+                ..Default::default()
+            })
+            .add_system(recycle_enemies);
+        // one in the 1200-pixel wide arena, and six out of it
+        spawn_enemy(&mut app, 0.0);
+        for _ in 0..6 {
+            spawn_enemy(&mut app, 1000.0);
+        }
+        app.update();
+
+        let stats = app.world.resource::<EnemyPool>().stats();
+        assert_eq!(
+            stats,
+            PoolStats {
+                live: 4,
+                peak: 7,
+                spawned: 7,
+                recycled: 3,
+                despawned: 3,
+            }
+        );
+        let mut enemies = app.world.query::<(&Character, &Enemy, &Hitbox)>();
+        assert_eq!(enemies.iter(&app.world).count(), 4);
+        let arena = Arena::default();
+        for (character, _, hitbox) in enemies.iter(&app.world) {
+            assert!(!has_left(&arena, character, hitbox));
+        }
+        // a recycled enemy can hit again
+        let fresh = enemies
+            .iter(&app.world)
+            .filter(|(_, enemy, _)| !enemy.collided)
+            .count();
+        assert_eq!(fresh, 3);
+
+        // back in the arena, they are left alone
+        app.update();
+        assert_eq!(app.world.resource::<EnemyPool>().stats(), stats);
+
+        app.world.resource_mut::<EnemyPool>().cleared();
+        let stats = app.world.resource::<EnemyPool>().stats();
+        assert_eq!((stats.live, stats.despawned), (0, 7));
+    }
+}
@@ -0,0 +1,197 @@
+use {
+    crate::{
+        character::Character,
+        clock::{GameClock, GameClockSystem},
+        damage::GameMode,
+        player::{animate_player, Player},
+        session::GameSession,
+        AppState, GameOverEvent, PlayerInput,
+    },
+    bevy::prelude::*,
+    serde::{Deserialize, Serialize},
+    std::{fs, io, path::PathBuf},
+};
+
+//
+// Replay: per-frame player input and the seed of a whole game session
+//
+#[derive(Clone, Copy, Debug, Default, Deserialize, Serialize)]
+pub struct ReplayFrame {
+    /// game time passed in this frame (in seconds)
+    pub delta: f32,
+    pub diff_x: f32,
+    pub diff_y: f32,
+    pub flip: bool,
+}
+
+#[derive(Debug, Default, Deserialize, Serialize)]
+pub struct Replay {
+    pub seed: u64,
+    #[serde(default)]
+    pub mode: GameMode,
+    pub frames: Vec<ReplayFrame>,
+    /// the high score when the game was over
+    pub final_score: f32,
+}
+
+impl Replay {
+    pub fn load(path: &PathBuf) -> io::Result<Self> {
+        ron::from_str(&fs::read_to_string(path)?)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+    }
+    pub fn save(&self, path: &PathBuf) -> io::Result<()> {
+        let text = ron::ser::to_string_pretty(self, ron::ser::PrettyConfig::default())
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        fs::write(path, text)
+    }
+}
+
+/// Insert this to save the first game session to `path`
+#[derive(Debug, Resource)]
+pub struct ReplayRecorder {
+    path: PathBuf,
+    replay: Replay,
+    done: bool,
+}
+
+impl ReplayRecorder {
+    pub fn new(path: PathBuf) -> Self {
+        Self {
+            path,
+            replay: Replay::default(),
+            done: false,
+        }
+    }
+}
+
+/// Insert this (and `GameRng::new(replay.seed)` and `replay.mode`) to play `replay` back instead of the player
+#[derive(Debug, Resource)]
+pub struct ReplayPlayer {
+    replay: Replay,
+    cursor: usize,
+}
+
+impl ReplayPlayer {
+    pub fn new(replay: Replay) -> Self {
+        Self { replay, cursor: 0 }
+    }
+    pub fn is_finished(&self) -> bool {
+        self.replay.frames.len() <= self.cursor
+    }
+}
+
+pub struct ReplayPlugin;
+
+impl Plugin for ReplayPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system_to_stage(CoreStage::First, feed_delta.before(GameClockSystem))
+            .add_system_set(
+                SystemSet::on_update(AppState::Game)
+                    .with_system(record_input.after(PlayerInput).before(animate_player))
+                    .with_system(feed_input.after(PlayerInput).before(animate_player)),
+            )
+            // after `SimulationStage`, where `check_collision` ends the game.
+            // It waits for `GameOverEvent`, so it needs no state of its own.
+            .add_system_to_stage(CoreStage::PostUpdate, finish_replay);
+    }
+}
+
+/// Replace the frame time with the recorded one; `GameClock` stands still once they run out
+fn feed_delta(
+    state: Res<State<AppState>>,
+    player: Option<Res<ReplayPlayer>>,
+    mut clock: ResMut<GameClock>,
+) {
+    if let (AppState::Game, Some(player)) = (state.current(), player) {
+        clock.feed(
+            player
+                .replay
+                .frames
+                .get(player.cursor)
+                .map_or(0.0, |frame| frame.delta),
+        );
+    }
+}
+
+fn record_input(
+    clock: Res<GameClock>,
+    recorder: Option<ResMut<ReplayRecorder>>,
+    player: Query<&Character, With<Player>>,
+) {
+    if let (Some(mut recorder), Ok(player)) = (recorder, player.get_single()) {
+        if recorder.done {
+            return;
+        }
+        recorder.replay.frames.push(ReplayFrame {
+            delta: clock.delta_seconds(),
+            diff_x: player.diff_x,
+            diff_y: player.diff_y,
+            flip: player.flip,
+        });
+    }
+}
+
+fn feed_input(
+    replay_player: Option<ResMut<ReplayPlayer>>,
+    mut player: Query<&mut Character, With<Player>>,
+) {
+    if let (Some(mut replay_player), Ok(mut player)) = (replay_player, player.get_single_mut()) {
+        if let Some(frame) = replay_player
+            .replay
+            .frames
+            .get(replay_player.cursor)
+            .copied()
+        {
+            player.diff_x = frame.diff_x;
+            player.diff_y = frame.diff_y;
+            player.flip = frame.flip;
+            replay_player.cursor += 1;
+        }
+    }
+}
+
+fn finish_replay(
+    session: Res<GameSession>,
+    mode: Res<GameMode>,
+    recorder: Option<ResMut<ReplayRecorder>>,
+    replay_player: Option<Res<ReplayPlayer>>,
+    mut game_over_event: EventReader<GameOverEvent>,
+) {
+    if game_over_event.iter().next().is_none() {
+        return;
+    }
+    let score = session.max_score;
+    if let Some(mut recorder) = recorder {
+        if !recorder.done {
+            recorder.done = true;
+            recorder.replay.seed = session.seed;
+            recorder.replay.mode = *mode;
+            recorder.replay.final_score = score;
+            match recorder.replay.save(&recorder.path) {
+                Ok(()) => info!(
+                    "saved a replay of {} frames to {:?}",
+                    recorder.replay.frames.len(),
+                    recorder.path
+                ),
+                Err(e) => error!("failed to save a replay to {:?}: {}", recorder.path, e),
+            }
+        }
+    }
+    if let Some(replay_player) = replay_player {
+        let replay = &replay_player.replay;
+        if replay_player.cursor == replay.frames.len() && score == replay.final_score {
+            info!(
+                "replay reproduced: game over at frame {}",
+                replay_player.cursor
+            );
+        } else {
+            warn!(
+                "replay diverged: game over at frame {} with {} (recorded: frame {} with {})",
+                replay_player.cursor,
+                score,
+                replay.frames.len(),
+                replay.final_score
+            );
+        }
+    }
+}
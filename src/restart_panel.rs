@@ -2,22 +2,49 @@
 
 use bevy::render::view::visibility;
 use {
-    crate::{player::Player, AppState, GameOverEvent, RestartEvent},
-    bevy::{app::AppExit, prelude::*},
+    crate::{
+        manifest::{GameAssets, UI_FONT},
+        session::GameSession,
+        settings::Setting,
+        AppState, GameOverEvent, RestartEvent,
+    },
+    bevy::{app::AppExit, ecs::system::EntityCommands, prelude::*},
 };
 
-const NORMAL_BUTTON: Color = Color::rgb(0.05, 0.05, 0.05);
-const HOVERED_BUTTON: Color = Color::rgb(0.05, 0.25, 0.95);
-const PRESSED_BUTTON: Color = Color::rgb(1.00, 0.25, 0.25);
+pub const NORMAL_BUTTON: Color = Color::rgb(0.05, 0.05, 0.05);
+pub const HOVERED_BUTTON: Color = Color::rgb(0.05, 0.25, 0.95);
+pub const PRESSED_BUTTON: Color = Color::rgb(1.00, 0.25, 0.25);
 
 #[derive(Component, Debug, Default)]
 pub struct GameButtonsPlugin;
 
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ButtonAction {
+    #[default]
+    Restart,
+    Exit,
+    Resume,
+    Start,
+    Options,
+    HighScores,
+    /// to the main menu
+    Back,
+    /// step a setting on the options screen
+    Cycle(Setting),
+}
+
+/// A `GameButton` was clicked, or chosen with the keyboard or a gamepad
+pub struct ButtonPressed(pub ButtonAction);
+
 #[derive(Component, Debug, Default)]
 pub struct GameButton {
-    exit: bool,
+    pub action: ButtonAction,
 }
 
+/// The buttons shown at game over
+#[derive(Component, Debug, Default)]
+pub struct RestartPanel;
+
 #[derive(Component, Debug, Default)]
 pub struct HighScoreLabel;
 
@@ -31,9 +58,9 @@ impl Plugin for GameButtonsPlugin {
 #[allow(clippy::type_complexity)]
 pub fn show_restart_panel(
     mut commands: Commands,
-    mut player: Query<&mut Player>,
+    session: Res<GameSession>,
     mut query: ParamSet<(
-        Query<&mut Style, With<GameButton>>,
+        Query<&mut Style, With<RestartPanel>>,
         Query<(&mut Style, &mut Text), With<HighScoreLabel>>,
     )>,
 ) {
@@ -42,10 +69,7 @@ pub fn show_restart_panel(
     }
     for (mut style, mut text) in query.p1().iter_mut() {
         style.display = Display::Flex;
-        text.sections[0].value = format!(
-            "Your high score is {:0>4.0}",
-            player.iter().next().map_or(0.0, |p| p.max_score)
-        );
+        text.sections[0].value = format!("Your high score is {:0>4.0}", session.max_score);
     }
 }
 
@@ -53,7 +77,7 @@ pub fn show_restart_panel(
 pub fn hide_restart_panel(
     mut commands: Commands,
     mut query: ParamSet<(
-        Query<&mut Style, With<GameButton>>,
+        Query<&mut Style, With<RestartPanel>>,
         Query<&mut Style, With<HighScoreLabel>>,
     )>,
 ) {
@@ -68,8 +92,7 @@ pub fn hide_restart_panel(
 #[allow(clippy::type_complexity)]
 pub fn restart_panel_system(
     mut commands: Commands,
-    mut app_exit_events: EventWriter<AppExit>,
-    mut restart_events: EventWriter<RestartEvent>,
+    mut pressed_events: EventWriter<ButtonPressed>,
     mut interaction_query: Query<
         (
             &Interaction,
@@ -85,11 +108,7 @@ pub fn restart_panel_system(
         match *interaction {
             Interaction::Clicked => {
                 *color = PRESSED_BUTTON.into();
-                if button.exit {
-                    app_exit_events.send(AppExit);
-                } else {
-                    restart_events.send(RestartEvent);
-                }
+                pressed_events.send(ButtonPressed(button.action));
             }
             Interaction::Hovered => {
                 *color = HOVERED_BUTTON.into();
@@ -101,12 +120,44 @@ pub fn restart_panel_system(
     }
 }
 
+pub fn perform_button_action(
+    mut pressed_events: EventReader<ButtonPressed>,
+    mut state: ResMut<State<AppState>>,
+    mut app_exit_events: EventWriter<AppExit>,
+    mut restart_events: EventWriter<RestartEvent>,
+) {
+    if let Some(ButtonPressed(action)) = pressed_events.iter().last() {
+        match action {
+            ButtonAction::Restart => restart_events.send(RestartEvent),
+            ButtonAction::Exit => app_exit_events.send(AppExit),
+            ButtonAction::Resume => {
+                let _ = state.pop();
+            }
+            // a change already queued this frame, e.g. by a click and `Return` together, wins
+            ButtonAction::Start => {
+                let _ = state.set(AppState::Game);
+            }
+            ButtonAction::Options => {
+                let _ = state.set(AppState::Options);
+            }
+            ButtonAction::HighScores => {
+                let _ = state.set(AppState::HighScores);
+            }
+            ButtonAction::Back => {
+                let _ = state.set(AppState::Menu);
+            }
+            // handled by `SettingsPlugin`
+            ButtonAction::Cycle(_) => (),
+        }
+    }
+}
+
 pub fn setup_restart_panel(
     mut commands: Commands,
-    mut player: Query<&mut Player>,
-    asset_server: Res<AssetServer>,
+    session: Res<GameSession>,
+    assets: Res<GameAssets>,
 ) {
-    let font = asset_server.load("fonts/Xolonium-Regular.ttf");
+    let font = assets.font(UI_FONT);
     let font_size = 40.0;
     commands
         .spawn(TextBundle {
@@ -121,10 +172,7 @@ pub fn setup_restart_panel(
                 ..Default::default()
             },
             text: Text::from_section(
-                format!(
-                    "Your high score is {:0>4}",
-                    player.iter().next().map_or(0.0, |p| p.max_score)
-                ),
+                format!("Your high score is {:0>4}", session.max_score),
                 TextStyle {
                     font: font.clone(),
                     font_size,
@@ -134,63 +182,75 @@ pub fn setup_restart_panel(
             ..Default::default()
         })
         .insert(HighScoreLabel);
-    commands
-        .spawn(ButtonBundle {
-            style: Style {
-                display: Display::None,
-                // position_type: PositionType::Absolute,
-                size: Size::new(Val::Px(250.0), Val::Px(80.0)),
-                // center button
-                margin: UiRect::all(Val::Auto),
-                // horizontally center child text
-                justify_content: JustifyContent::Center,
-                // vertically center child text
-                align_items: AlignItems::Center,
-                ..Default::default()
-            },
-            background_color: NORMAL_BUTTON.into(),
-            ..Default::default()
-        })
-        .with_children(|parent| {
-            parent.spawn(TextBundle {
-                text: Text::from_section(
-                    "Restart",
-                    TextStyle {
-                        font: font.clone(),
-                        font_size,
-                        color: Color::rgb(0.6, 0.9, 0.8),
-                    },
-                ),
-                ..Default::default()
-            });
-        })
-        .insert(GameButton { exit: false });
-    commands
-        .spawn(ButtonBundle {
-            style: Style {
-                display: Display::None,
-                // position_type: PositionType::Absolute,
-                size: Size::new(Val::Px(250.0), Val::Px(80.0)),
-                margin: UiRect::all(Val::Auto),
-                justify_content: JustifyContent::Center,
-                align_items: AlignItems::Center,
-                ..Default::default()
-            },
-            background_color: NORMAL_BUTTON.into(),
-            ..Default::default()
-        })
+    spawn_button(
+        &mut commands,
+        font.clone(),
+        "Restart",
+        Color::rgb(0.6, 0.9, 0.8),
+        ButtonAction::Restart,
+    )
+    .insert(RestartPanel);
+    spawn_button(
+        &mut commands,
+        font,
+        "Exit",
+        Color::rgb(1.0, 0.5, 0.5),
+        ButtonAction::Exit,
+    )
+    .insert(RestartPanel);
+}
+
+/// A hidden button labelled `label` in `color`
+pub fn spawn_button<'w, 's, 'a>(
+    commands: &'a mut Commands<'w, 's>,
+    font: Handle<Font>,
+    label: &str,
+    color: Color,
+    action: ButtonAction,
+) -> EntityCommands<'w, 's, 'a> {
+    let style = Style {
+        display: Display::None,
+        // position_type: PositionType::Absolute,
+        size: Size::new(Val::Px(250.0), Val::Px(80.0)),
+        // center button
+        margin: UiRect::all(Val::Auto),
+        // horizontally center child text
+        justify_content: JustifyContent::Center,
+        // vertically center child text
+        align_items: AlignItems::Center,
+        ..Default::default()
+    };
+    spawn_button_with_style(commands, style, font, label, color, action)
+}
+
+/// `spawn_button` laid out by `style`
+pub fn spawn_button_with_style<'w, 's, 'a>(
+    commands: &'a mut Commands<'w, 's>,
+    style: Style,
+    font: Handle<Font>,
+    label: &str,
+    color: Color,
+    action: ButtonAction,
+) -> EntityCommands<'w, 's, 'a> {
+    let mut button = commands.spawn(ButtonBundle {
+        style,
+        background_color: NORMAL_BUTTON.into(),
+        ..Default::default()
+    });
+    button
         .with_children(|parent| {
             parent.spawn(TextBundle {
                 text: Text::from_section(
-                    "Exit",
+                    label,
                     TextStyle {
                         font,
-                        font_size,
-                        color: Color::rgb(1.0, 0.5, 0.5),
+                        font_size: 40.0,
+                        color,
                     },
                 ),
                 ..Default::default()
             });
         })
-        .insert(GameButton { exit: true });
+        .insert(GameButton { action });
+    button
 }
@@ -0,0 +1,53 @@
+use {
+    bevy::prelude::*,
+    rand::{rngs::StdRng, RngCore, SeedableRng},
+};
+
+//
+// Random number generator
+//
+/// The source of every random decision in the game.
+/// A run is reproducible from its seed.
+#[derive(Debug, Resource)]
+pub struct GameRng {
+    pub seed: u64,
+    rng: StdRng,
+}
+
+impl Default for GameRng {
+    fn default() -> Self {
+        Self::new(rand::random())
+    }
+}
+
+impl GameRng {
+    pub fn new(seed: u64) -> Self {
+        Self {
+            seed,
+            rng: StdRng::seed_from_u64(seed),
+        }
+    }
+    /// rewind to the beginning of the sequence for `seed`
+    pub fn reseed(&mut self, seed: u64) {
+        *self = Self::new(seed);
+    }
+}
+
+impl RngCore for GameRng {
+    fn next_u32(&mut self) -> u32 {
+        self.rng.next_u32()
+    }
+    fn next_u64(&mut self) -> u64 {
+        self.rng.next_u64()
+    }
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        self.rng.fill_bytes(dest)
+    }
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), rand::Error> {
+        self.rng.try_fill_bytes(dest)
+    }
+}
+
+pub fn report_seed(rng: Res<GameRng>) {
+    info!("random seed: {}", rng.seed);
+}
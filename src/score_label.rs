@@ -1,19 +1,39 @@
 use {
-    crate::{player::Player, AppState},
+    crate::{
+        damage::{GameMode, Vitality, LIVES, MAX_HEALTH},
+        manifest::{GameAssets, UI_FONT},
+        player::PLAYER_SPRITES,
+        session::GameSession,
+        AppState, WaveEvent,
+    },
     bevy::prelude::*,
 };
 pub struct ScorePlugin;
 
 impl Plugin for ScorePlugin {
     fn build(&self, app: &mut App) {
-        app.add_startup_system(setup_simple);
+        app.add_system_set(
+            SystemSet::on_enter(AppState::Setup)
+                .with_system(setup_simple)
+                .with_system(setup_wave_label)
+                .with_system(setup_vitality_hud),
+        )
+        .add_system(announce_wave)
+        .add_system_set(SystemSet::on_enter(AppState::Game).with_system(reset_score_label))
+        .add_system(update_vitality_hud);
     }
 }
 
 #[derive(Component)]
 pub struct ScoreLabel;
 
-fn setup_simple(mut commands: Commands, asset_server: Res<AssetServer>) {
+/// "Wave N", shown for a while when a wave begins
+#[derive(Component)]
+pub struct WaveLabel {
+    timer: Timer,
+}
+
+fn setup_simple(mut commands: Commands, assets: Res<GameAssets>) {
     // Rich text with multiple sections
     commands
         .spawn(TextBundle {
@@ -34,7 +54,7 @@ fn setup_simple(mut commands: Commands, asset_server: Res<AssetServer>) {
                     TextSection {
                         value: "Score: ".to_string(),
                         style: TextStyle {
-                            font: asset_server.load("fonts/Xolonium-Regular.ttf"),
+                            font: assets.font(UI_FONT),
                             font_size: 60.0,
                             color: Color::WHITE,
                         },
@@ -42,7 +62,7 @@ fn setup_simple(mut commands: Commands, asset_server: Res<AssetServer>) {
                     TextSection {
                         value: "".to_string(),
                         style: TextStyle {
-                            font: asset_server.load("fonts/Xolonium-Regular.ttf"),
+                            font: assets.font(UI_FONT),
                             font_size: 60.0,
                             color: Color::GOLD,
                         },
@@ -55,6 +75,149 @@ fn setup_simple(mut commands: Commands, asset_server: Res<AssetServer>) {
         .insert(ScoreLabel);
 }
 
+/// One of the remaining lives in `GameMode::Lives`, drawn as the player
+#[derive(Component)]
+pub struct LifeIcon(u32);
+
+/// The frame of the health bar in `GameMode::Health`
+#[derive(Component)]
+pub struct HealthBar;
+
+/// The filled part of `HealthBar`
+#[derive(Component)]
+pub struct HealthFill;
+
+fn setup_vitality_hud(mut commands: Commands, assets: Res<GameAssets>) {
+    commands
+        .spawn(NodeBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                position: UiRect {
+                    top: Val::Px(15.0),
+                    right: Val::Px(15.0),
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+            ..Default::default()
+        })
+        .with_children(|parent| {
+            for i in 0..LIVES {
+                parent
+                    .spawn(ImageBundle {
+                        style: Style {
+                            display: Display::None,
+                            size: Size::new(Val::Px(48.0), Val::Px(48.0)),
+                            margin: UiRect::all(Val::Px(4.0)),
+                            ..Default::default()
+                        },
+                        image: assets
+                            .sprite(PLAYER_SPRITES[0])
+                            .cloned()
+                            .unwrap_or_default()
+                            .into(),
+                        ..Default::default()
+                    })
+                    .insert(LifeIcon(i));
+            }
+            parent
+                .spawn(NodeBundle {
+                    style: Style {
+                        display: Display::None,
+                        size: Size::new(Val::Px(300.0), Val::Px(28.0)),
+                        padding: UiRect::all(Val::Px(4.0)),
+                        ..Default::default()
+                    },
+                    background_color: Color::rgba(0.0, 0.0, 0.0, 0.5).into(),
+                    ..Default::default()
+                })
+                .insert(HealthBar)
+                .with_children(|bar| {
+                    bar.spawn(NodeBundle {
+                        style: Style {
+                            size: Size::new(Val::Percent(100.0), Val::Percent(100.0)),
+                            ..Default::default()
+                        },
+                        background_color: Color::ORANGE_RED.into(),
+                        ..Default::default()
+                    })
+                    .insert(HealthFill);
+                });
+        });
+}
+
+/// Show lives or health, whichever the game mode counts
+#[allow(clippy::type_complexity)]
+fn update_vitality_hud(
+    mode: Res<GameMode>,
+    vitality: Query<&Vitality>,
+    mut hud: ParamSet<(
+        Query<(&mut Style, &LifeIcon)>,
+        Query<&mut Style, With<HealthBar>>,
+        Query<&mut Style, With<HealthFill>>,
+    )>,
+) {
+    let vitality = match vitality.get_single() {
+        Ok(vitality) => vitality,
+        Err(_) => return,
+    };
+    let display = |shown: bool| if shown { Display::Flex } else { Display::None };
+    for (mut style, LifeIcon(i)) in hud.p0().iter_mut() {
+        style.display = display(*mode == GameMode::Lives && *i < vitality.lives);
+    }
+    for mut style in hud.p1().iter_mut() {
+        style.display = display(*mode == GameMode::Health);
+    }
+    for mut style in hud.p2().iter_mut() {
+        style.size.width = Val::Percent(100.0 * vitality.health / MAX_HEALTH);
+    }
+}
+
+fn setup_wave_label(mut commands: Commands, assets: Res<GameAssets>) {
+    commands
+        .spawn(TextBundle {
+            style: Style {
+                display: Display::None,
+                position_type: PositionType::Absolute,
+                position: UiRect {
+                    left: Val::Percent(38.0),
+                    top: Val::Percent(40.0),
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+            text: Text::from_section(
+                "",
+                TextStyle {
+                    font: assets.font(UI_FONT),
+                    font_size: 80.0,
+                    color: Color::ORANGE_RED,
+                },
+            ),
+            ..Default::default()
+        })
+        .insert(WaveLabel {
+            timer: Timer::from_seconds(2.0, TimerMode::Once),
+        });
+}
+
+fn announce_wave(
+    time: Res<Time>,
+    mut wave_event: EventReader<WaveEvent>,
+    mut query: Query<(&mut Style, &mut Text, &mut WaveLabel)>,
+) {
+    let wave = wave_event.iter().last();
+    for (mut style, mut text, mut label) in query.iter_mut() {
+        if let Some(WaveEvent(n)) = wave {
+            text.sections[0].value = format!("Wave {}", n);
+            style.display = Display::Flex;
+            label.timer.reset();
+        } else if style.display == Display::Flex && label.timer.tick(time.delta()).finished() {
+            style.display = Display::None;
+        }
+    }
+}
+
 pub fn simple_text_update(time: Res<Time>, mut query: Query<&mut Text, With<ScoreLabel>>) {
     let seconds = time.raw_elapsed_seconds();
     for mut text in query.iter_mut() {
@@ -62,18 +225,33 @@ pub fn simple_text_update(time: Res<Time>, mut query: Query<&mut Text, With<Scor
     }
 }
 
+/// A point for every fifth of a second survived, at 60 ticks per second
+const TICKS_PER_POINT: u32 = 12;
+
+/// In `SimulationStage` after `advance_session`, so that a replay scores alike
 pub fn update_score(
-    state: ResMut<State<AppState>>,
-    mut player_query: Query<&mut Player>,
+    mut session: ResMut<GameSession>,
     mut score_query: Query<&mut Text, With<ScoreLabel>>,
 ) {
-    if *state.current() != AppState::Game {
+    if !session.ticks.is_multiple_of(TICKS_PER_POINT) {
         return;
     }
-    if let Some(mut player) = player_query.iter_mut().next() {
-        player.score += 1.0;
-        player.max_score = player.max_score.max(player.score);
-        let mut score = score_query.single_mut();
-        score.sections[1].value = format!("{:0>4}", player.score as u32);
+    session.score += 1.0;
+    session.max_score = session.max_score.max(session.score);
+    if let Ok(mut score) = score_query.get_single_mut() {
+        score.sections[1].value = format!("{:0>4}", session.score as u32);
+    }
+}
+
+/// Clear the score of the last run and its wave announcement
+fn reset_score_label(
+    mut score_query: Query<&mut Text, With<ScoreLabel>>,
+    mut wave_query: Query<&mut Style, With<WaveLabel>>,
+) {
+    for mut score in score_query.iter_mut() {
+        score.sections[1].value = format!("{:0>4}", 0);
+    }
+    for mut style in wave_query.iter_mut() {
+        style.display = Display::None;
     }
 }
@@ -0,0 +1,97 @@
+use {
+    crate::{
+        atlas::SpriteAtlases,
+        character::{Character, SpawnTimer},
+        collision::HitRecovery,
+        damage::Vitality,
+        player::Player,
+        rng::GameRng,
+        SIMULATION_TICK,
+    },
+    bevy::prelude::*,
+    rand::RngCore,
+};
+
+//
+// Game session: the state of one run, from the start to game over
+//
+#[derive(Debug, Default, Resource)]
+pub struct GameSession {
+    /// 1-based count of the runs since launch
+    pub run: u32,
+    /// `GameRng` was reseeded with this at the start
+    pub seed: u64,
+    pub score: f32,
+    /// the best score in this run
+    pub max_score: f32,
+    /// seconds of simulation
+    pub elapsed: f32,
+    /// simulation ticks, which `elapsed` is made of
+    pub ticks: u32,
+    /// 1-based, following `WaveDirector`
+    pub wave: usize,
+    /// hits taken
+    pub hits: u32,
+}
+
+impl GameSession {
+    /// Start the next run. The first keeps the seed `GameRng` was made with,
+    /// so that `--seed` and replays reproduce it; the later ones draw theirs from it.
+    pub fn begin(&mut self, rng: &mut GameRng) {
+        let seed = if self.run == 0 {
+            rng.seed
+        } else {
+            rng.next_u64()
+        };
+        rng.reseed(seed);
+        *self = GameSession {
+            run: self.run + 1,
+            seed,
+            wave: 1,
+            ..Default::default()
+        };
+    }
+}
+
+/// Begin a session and put the player back to the center, unhurt and still
+#[allow(clippy::type_complexity)]
+pub fn reset_session(
+    mut session: ResMut<GameSession>,
+    mut rng: ResMut<GameRng>,
+    atlases: Res<SpriteAtlases>,
+    mut player: Query<
+        (
+            &mut Character,
+            &mut Transform,
+            &mut SpawnTimer,
+            &mut TextureAtlasSprite,
+            &mut HitRecovery,
+            &mut Vitality,
+        ),
+        With<Player>,
+    >,
+) {
+    session.begin(&mut rng);
+    info!("run {} with random seed: {}", session.run, session.seed);
+    for (mut character, mut trans, mut timer, mut sprite, mut recovery, mut vitality) in
+        player.iter_mut()
+    {
+        character.warp_to(0.0, 0.0);
+        character.diff_x = 0.0;
+        character.diff_y = 0.0;
+        character.flip = false;
+        trans.translation.x = 0.0;
+        trans.translation.y = 0.0;
+        timer.reset();
+        sprite.index = atlases.player.first;
+        sprite.flip_x = false;
+        sprite.color.set_a(1.0);
+        *recovery = HitRecovery::default();
+        *vitality = Vitality::default();
+    }
+}
+
+pub fn advance_session(mut session: ResMut<GameSession>) {
+    session.elapsed += SIMULATION_TICK as f32;
+    session.ticks += 1;
+}
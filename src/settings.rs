@@ -0,0 +1,284 @@
+use {
+    crate::{
+        control::{ControlScheme, TouchSteering},
+        manifest::{GameAssets, UI_FONT},
+        menu::Screen,
+        restart_panel::{spawn_button_with_style, ButtonAction, ButtonPressed, GameButton},
+        AppState,
+    },
+    bevy::{prelude::*, window::WindowMode},
+    serde::{Deserialize, Serialize},
+    std::{fs, io, path::PathBuf},
+};
+
+//
+// Settings, kept in the platform's config directory and edited on the options screen
+//
+pub const RESOLUTIONS: [(f32, f32); 4] = [
+    (1200.0, 800.0),
+    (1280.0, 720.0),
+    (1600.0, 900.0),
+    (1920.0, 1080.0),
+];
+const VOLUMES: [f32; 5] = [0.0, 0.25, 0.5, 0.75, 1.0];
+const SHAKES: [f32; 3] = [0.0, 0.5, 1.0];
+
+#[derive(Clone, Debug, Deserialize, Resource, Serialize)]
+#[serde(default)]
+pub struct Settings {
+    /// the window size in windowed mode
+    pub resolution: (f32, f32),
+    pub fullscreen: bool,
+    /// 0 to 1
+    pub music_volume: f32,
+    /// 0 to 1
+    pub sfx_volume: f32,
+    pub control_scheme: ControlScheme,
+    /// see `TouchSteering::relative`
+    pub relative_touch: bool,
+    /// 0 (off) to 1
+    pub screen_shake: f32,
+    /// where it is saved; `None` keeps it in memory
+    #[serde(skip)]
+    pub path: Option<PathBuf>,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            resolution: RESOLUTIONS[0],
+            fullscreen: false,
+            music_volume: 1.0,
+            sfx_volume: 1.0,
+            control_scheme: ControlScheme::default(),
+            relative_touch: false,
+            screen_shake: 1.0,
+            path: None,
+        }
+    }
+}
+
+/// The next one in `values` after `current`, wrapping around
+fn cycle<T: Copy + PartialEq>(values: &[T], current: T) -> T {
+    let i = values
+        .iter()
+        .position(|v| *v == current)
+        .map_or(0, |i| i + 1);
+    values[i % values.len()]
+}
+
+impl Settings {
+    /// e.g. `~/.config/dodge-the-bevy/settings.ron` on Linux
+    pub fn default_path() -> Option<PathBuf> {
+        dirs::config_dir().map(|dir| dir.join("dodge-the-bevy").join("settings.ron"))
+    }
+    /// from `default_path`, or the defaults
+    pub fn load() -> Self {
+        let path = match Self::default_path() {
+            Some(path) => path,
+            None => return Settings::default(),
+        };
+        let loaded = fs::read_to_string(&path).and_then(|text| {
+            ron::from_str::<Settings>(&text)
+                .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+        });
+        let mut settings = match loaded {
+            Ok(settings) => settings,
+            Err(e) => {
+                if e.kind() != io::ErrorKind::NotFound {
+                    warn!("failed to load settings from {:?}: {}", path, e);
+                }
+                Settings::default()
+            }
+        };
+        settings.path = Some(path);
+        settings
+    }
+    pub fn save(&self) -> io::Result<()> {
+        let path = match &self.path {
+            Some(path) => path,
+            None => return Ok(()),
+        };
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let text = ron::ser::to_string_pretty(self, ron::ser::PrettyConfig::default())
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        fs::write(path, text)
+    }
+    pub fn window_mode(&self) -> WindowMode {
+        if self.fullscreen {
+            WindowMode::BorderlessFullscreen
+        } else {
+            WindowMode::Windowed
+        }
+    }
+    /// step `setting` to its next value
+    pub fn cycle(&mut self, setting: Setting) {
+        match setting {
+            Setting::Resolution => self.resolution = cycle(&RESOLUTIONS, self.resolution),
+            Setting::Fullscreen => self.fullscreen = !self.fullscreen,
+            Setting::MusicVolume => self.music_volume = cycle(&VOLUMES, self.music_volume),
+            Setting::SfxVolume => self.sfx_volume = cycle(&VOLUMES, self.sfx_volume),
+            Setting::ControlScheme => self.control_scheme = self.control_scheme.next(),
+            Setting::TouchSteering => self.relative_touch = !self.relative_touch,
+            Setting::ScreenShake => self.screen_shake = cycle(&SHAKES, self.screen_shake),
+        }
+    }
+    /// the label of the button for `setting`
+    pub fn describe(&self, setting: Setting) -> String {
+        let percent = |v: f32| format!("{:.0}%", 100.0 * v);
+        match setting {
+            Setting::Resolution => {
+                format!("Resolution: {}x{}", self.resolution.0, self.resolution.1)
+            }
+            Setting::Fullscreen => {
+                format!("Fullscreen: {}", if self.fullscreen { "on" } else { "off" })
+            }
+            Setting::MusicVolume => format!("Music: {}", percent(self.music_volume)),
+            Setting::SfxVolume => format!("Sound: {}", percent(self.sfx_volume)),
+            Setting::ControlScheme => format!("Control: {:?}", self.control_scheme),
+            Setting::TouchSteering => format!(
+                "Touch: {}",
+                if self.relative_touch {
+                    "relative"
+                } else {
+                    "follow finger"
+                }
+            ),
+            Setting::ScreenShake => format!("Screen shake: {}", percent(self.screen_shake)),
+        }
+    }
+}
+
+/// The items on the options screen
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Setting {
+    Resolution,
+    Fullscreen,
+    MusicVolume,
+    SfxVolume,
+    ControlScheme,
+    TouchSteering,
+    ScreenShake,
+}
+
+const SETTINGS: [Setting; 7] = [
+    Setting::Resolution,
+    Setting::Fullscreen,
+    Setting::MusicVolume,
+    Setting::SfxVolume,
+    Setting::ControlScheme,
+    Setting::TouchSteering,
+    Setting::ScreenShake,
+];
+
+/// The systems for `DodgePlugin`. Insert `Settings` beforehand to skip loading them.
+pub struct SettingsPlugin;
+
+impl Plugin for SettingsPlugin {
+    fn build(&self, app: &mut App) {
+        if !app.world.contains_resource::<Settings>() {
+            app.insert_resource(Settings::load());
+        }
+        app.add_system_set(SystemSet::on_enter(AppState::Setup).with_system(setup_options))
+            .add_system(change_setting)
+            .add_system(label_settings.after(change_setting))
+            .add_system(apply_settings.after(change_setting));
+    }
+}
+
+fn setup_options(mut commands: Commands, assets: Res<GameAssets>, settings: Res<Settings>) {
+    let font = assets.font(UI_FONT);
+    let rows = SETTINGS
+        .iter()
+        .map(|setting| {
+            (
+                settings.describe(*setting),
+                Color::rgb(0.9, 0.9, 0.6),
+                ButtonAction::Cycle(*setting),
+            )
+        })
+        .chain([(
+            "Back".to_string(),
+            Color::rgb(0.6, 0.9, 0.8),
+            ButtonAction::Back,
+        )]);
+    for (i, (label, color, action)) in rows.enumerate() {
+        let style = Style {
+            display: Display::None,
+            position_type: PositionType::Absolute,
+            position: UiRect {
+                left: Val::Percent(25.0),
+                top: Val::Percent(26.0 + 8.5 * i as f32),
+                ..Default::default()
+            },
+            size: Size::new(Val::Percent(50.0), Val::Percent(7.5)),
+            justify_content: JustifyContent::Center,
+            align_items: AlignItems::Center,
+            ..Default::default()
+        };
+        spawn_button_with_style(&mut commands, style, font.clone(), &label, color, action)
+            .insert(Screen(AppState::Options));
+    }
+}
+
+fn change_setting(mut pressed_events: EventReader<ButtonPressed>, mut settings: ResMut<Settings>) {
+    let mut changed = false;
+    for ButtonPressed(action) in pressed_events.iter() {
+        if let ButtonAction::Cycle(setting) = action {
+            settings.cycle(*setting);
+            changed = true;
+        }
+    }
+    if changed {
+        if let Err(e) = settings.save() {
+            error!("failed to save settings to {:?}: {}", settings.path, e);
+        }
+    }
+}
+
+fn label_settings(
+    settings: Res<Settings>,
+    buttons: Query<(&GameButton, &Children)>,
+    mut texts: Query<&mut Text>,
+) {
+    if !settings.is_changed() {
+        return;
+    }
+    for (button, children) in buttons.iter() {
+        if let ButtonAction::Cycle(setting) = button.action {
+            for child in children.iter() {
+                if let Ok(mut text) = texts.get_mut(*child) {
+                    text.sections[0].value = settings.describe(setting);
+                }
+            }
+        }
+    }
+}
+
+/// Carry the settings over to the window and the controls.
+/// `SoundPlugin` follows the volumes by itself.
+fn apply_settings(
+    settings: Res<Settings>,
+    mut windows: ResMut<Windows>,
+    mut scheme: ResMut<ControlScheme>,
+    mut steering: ResMut<TouchSteering>,
+) {
+    if !settings.is_changed() {
+        return;
+    }
+    if let Some(window) = windows.get_primary_mut() {
+        if window.mode() != settings.window_mode() {
+            window.set_mode(settings.window_mode());
+        }
+        let (width, height) = settings.resolution;
+        if !settings.fullscreen
+            && (window.requested_width(), window.requested_height()) != (width, height)
+        {
+            window.set_resolution(width, height);
+        }
+    }
+    *scheme = settings.control_scheme;
+    steering.relative = settings.relative_touch;
+}
@@ -0,0 +1,217 @@
+use {
+    crate::{manifest::GameAssets, settings::Settings, AppState},
+    bevy::{audio::AudioSink, prelude::*, utils::Instant},
+    std::sync::Arc,
+};
+
+//
+// Music and sound effects, on their own volume buses
+//
+/// Seconds for a track to fade in or out
+const CROSSFADE: f32 = 2.0;
+/// A faded-out voice whose sink hasn't appeared by then is given up (in seconds)
+const SINK_TIMEOUT: f32 = 10.0;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Track {
+    /// the title screen and its menus
+    Menu,
+    Game,
+}
+
+impl Track {
+    pub const ALL: [Track; 2] = [Track::Menu, Track::Game];
+
+    /// its name in the asset manifest
+    pub fn name(self) -> &'static str {
+        match self {
+            Track::Menu => "menu_music",
+            Track::Game => "game_music",
+        }
+    }
+    /// the track for `state`; none while loading
+    pub fn for_state(state: &AppState) -> Option<Self> {
+        match state {
+            AppState::Load | AppState::LoadFailed | AppState::Setup => None,
+            AppState::Menu | AppState::Options | AppState::HighScores => Some(Track::Menu),
+            AppState::Game | AppState::Paused | AppState::Restart => Some(Track::Game),
+        }
+    }
+}
+
+/// A looping track and its fade
+#[derive(Debug)]
+struct Voice {
+    track: Track,
+    /// strong, so that the sink lives until it is faded out
+    sink: Handle<AudioSink>,
+    /// 0 to 1, before the music bus
+    gain: f32,
+    started: Instant,
+}
+
+/// The music on the music bus: the track for the current state and the ones fading out
+#[derive(Debug, Default, Resource)]
+pub struct MusicPlayer {
+    current: Option<Voice>,
+    fading: Vec<Voice>,
+    /// fades go on in real time, as `Time` stands still while paused
+    last_frame: Option<Instant>,
+}
+
+impl MusicPlayer {
+    pub fn current_track(&self) -> Option<Track> {
+        self.current.as_ref().map(|voice| voice.track)
+    }
+}
+
+/// Play a sound effect by its name in the asset manifest, e.g. `"hit"`, on the SFX bus
+pub struct SoundEffect(pub &'static str);
+
+/// The sound effects the game plays, checked against the asset manifest while loading
+pub const SOUND_EFFECTS: [&str; 1] = ["hit"];
+
+/// The systems for `DodgePlugin`. The bus volumes come from `Settings`; both are muted while paused.
+pub struct SoundPlugin;
+
+impl Plugin for SoundPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<MusicPlayer>()
+            .add_event::<SoundEffect>()
+            .add_system(play_music)
+            .add_system(play_sound_effects);
+    }
+}
+
+/// A tenth of a second of silence in WAV
+fn silence() -> AudioSource {
+    const RATE: u32 = 8_000;
+    const SAMPLES: u32 = RATE / 10;
+    let data_len = 2 * SAMPLES;
+    let mut bytes = Vec::with_capacity(44 + data_len as usize);
+    bytes.extend_from_slice(b"RIFF");
+    bytes.extend_from_slice(&(36 + data_len).to_le_bytes());
+    bytes.extend_from_slice(b"WAVEfmt ");
+    bytes.extend_from_slice(&16u32.to_le_bytes());
+    // PCM, mono, 16 bits
+    bytes.extend_from_slice(&1u16.to_le_bytes());
+    bytes.extend_from_slice(&1u16.to_le_bytes());
+    bytes.extend_from_slice(&RATE.to_le_bytes());
+    bytes.extend_from_slice(&(2 * RATE).to_le_bytes());
+    bytes.extend_from_slice(&2u16.to_le_bytes());
+    bytes.extend_from_slice(&16u16.to_le_bytes());
+    bytes.extend_from_slice(b"data");
+    bytes.extend_from_slice(&data_len.to_le_bytes());
+    bytes.resize(44 + data_len as usize, 0);
+    AudioSource {
+        bytes: Arc::from(bytes),
+    }
+}
+
+/// The sound named `name`, or silence in its place when it is missing
+fn source_or_silence(
+    name: &'static str,
+    assets: &GameAssets,
+    sources: &mut Assets<AudioSource>,
+) -> Handle<AudioSource> {
+    match assets.sound(name) {
+        Some(handle) => {
+            // `AppState::Load` has waited for it, so it is missing for good
+            if sources.get(handle).is_none() {
+                warn!("sound \"{}\" is missing; playing silence instead", name);
+                sources.set_untracked(handle, silence());
+            }
+            handle.clone()
+        }
+        None => {
+            warn!(
+                "sound \"{}\" is not in the asset manifest; playing silence instead",
+                name
+            );
+            sources.add(silence())
+        }
+    }
+}
+
+/// Crossfade to the track for the current state, and keep every voice at its volume
+fn play_music(
+    state: Res<State<AppState>>,
+    settings: Res<Settings>,
+    assets: Res<GameAssets>,
+    audio: Res<Audio>,
+    mut sources: ResMut<Assets<AudioSource>>,
+    audio_sinks: Res<Assets<AudioSink>>,
+    mut player: ResMut<MusicPlayer>,
+) {
+    let now = Instant::now();
+    let delta = player
+        .last_frame
+        .map_or(0.0, |last| now.duration_since(last).as_secs_f32());
+    player.last_frame = Some(now);
+
+    let wanted = Track::for_state(state.current());
+    if player.current_track() != wanted {
+        if let Some(voice) = player.current.take() {
+            player.fading.push(voice);
+        }
+        player.current = wanted.map(|track| {
+            let sink = audio.play_with_settings(
+                source_or_silence(track.name(), &assets, &mut sources),
+                PlaybackSettings::LOOP.with_volume(0.0),
+            );
+            Voice {
+                track,
+                sink: audio_sinks.get_handle(sink),
+                gain: 0.0,
+                started: now,
+            }
+        });
+    }
+
+    let step = delta / CROSSFADE;
+    let muted = *state.current() == AppState::Paused;
+    if let Some(voice) = player.current.as_mut() {
+        voice.gain = if muted {
+            0.0
+        } else {
+            (voice.gain + step).min(1.0)
+        };
+        if let Some(sink) = audio_sinks.get(&voice.sink) {
+            sink.set_volume(voice.gain * settings.music_volume);
+        }
+    }
+    player.fading.retain_mut(|voice| {
+        voice.gain -= step;
+        match audio_sinks.get(&voice.sink) {
+            Some(sink) if voice.gain <= 0.0 => {
+                sink.stop();
+                false
+            }
+            Some(sink) => {
+                sink.set_volume(voice.gain * settings.music_volume);
+                true
+            }
+            // not started yet; keep it to stop once it does, unless its source never loads
+            None => now.duration_since(voice.started).as_secs_f32() < SINK_TIMEOUT,
+        }
+    });
+}
+
+fn play_sound_effects(
+    state: Res<State<AppState>>,
+    settings: Res<Settings>,
+    assets: Res<GameAssets>,
+    audio: Res<Audio>,
+    mut sources: ResMut<Assets<AudioSource>>,
+    mut events: EventReader<SoundEffect>,
+) {
+    let muted = *state.current() == AppState::Paused;
+    for SoundEffect(name) in events.iter() {
+        if !muted {
+            audio.play_with_settings(
+                source_or_silence(name, &assets, &mut sources),
+                PlaybackSettings::ONCE.with_volume(settings.sfx_volume),
+            );
+        }
+    }
+}
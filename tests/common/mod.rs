@@ -0,0 +1,42 @@
+use {
+    bevy::prelude::*,
+    dodge_the_bevy::{
+        replay::{Replay, ReplayFrame, ReplayPlayer},
+        rng::GameRng,
+        AppState, HeadlessPlugin,
+    },
+    std::time::{Duration, Instant},
+};
+
+/// A headless game from `seed` whose clock and player input come from a replay
+/// of `frames` frames, each a 60th of a second long and moving as `input`.
+/// The clock stands still once they run out.
+/// It returns at the first frame of the game, once the tables are loaded.
+pub fn headless_app(seed: u64, input: ReplayFrame, frames: usize) -> App {
+    let replay = Replay {
+        seed,
+        frames: vec![
+            ReplayFrame {
+                delta: 1.0 / 60.0,
+                ..input
+            };
+            frames
+        ],
+        ..Default::default()
+    };
+    let mut app = App::new();
+    app.add_plugins(MinimalPlugins)
+        .insert_resource(GameRng::new(seed))
+        .insert_resource(ReplayPlayer::new(replay))
+        .add_plugin(HeadlessPlugin);
+    let started = Instant::now();
+    while app.world.resource::<State<AppState>>().current() == &AppState::Setup {
+        assert!(
+            started.elapsed() < Duration::from_secs(10),
+            "the tables didn't load"
+        );
+        std::thread::sleep(Duration::from_millis(1));
+        app.update();
+    }
+    app
+}
@@ -0,0 +1,95 @@
+use {
+    bevy::{
+        input::{
+            touch::{TouchInput, TouchPhase},
+            InputPlugin,
+        },
+        prelude::*,
+    },
+    dodge_the_bevy::{
+        atlas::SpriteAtlas,
+        character::Character,
+        control::{ControlPlugin, TouchSteering},
+        player::{Player, PLAYER_SPEED},
+        AppState, Arena,
+    },
+};
+
+/// The controls alone, in the default 1200x800 arena without a window nor a camera,
+/// and a player at its centre
+fn touch_app(relative: bool) -> App {
+    let mut app = App::new();
+    app.add_plugins(MinimalPlugins)
+        .add_plugin(InputPlugin)
+        .init_resource::<Windows>()
+        .init_resource::<Arena>()
+        .add_state(AppState::Game)
+        .add_plugin(ControlPlugin);
+    app.world.resource_mut::<TouchSteering>().relative = relative;
+    app.world.spawn((
+        Character::from(&SpriteAtlas::default()).with_position(0.0, 0.0),
+        Player,
+    ));
+    app.update();
+    app
+}
+
+/// At (`x`, `y`) from the top left corner of the window
+fn touch(app: &mut App, phase: TouchPhase, x: f32, y: f32) {
+    app.world.send_event(TouchInput {
+        phase,
+        position: Vec2::new(x, y),
+        force: None,
+        id: 0,
+    });
+    app.update();
+}
+
+/// The velocity and the flip of the player
+fn player_input(app: &mut App) -> (f32, f32, bool) {
+    let player = app
+        .world
+        .query_filtered::<&Character, With<Player>>()
+        .single(&app.world);
+    (player.diff_x, player.diff_y, player.flip)
+}
+
+fn assert_input(app: &mut App, diff_x: f32, diff_y: f32, flip: bool) {
+    let (x, y, f) = player_input(app);
+    assert!(
+        (x - diff_x).abs() < 1e-3 && (y - diff_y).abs() < 1e-3 && f == flip,
+        "expected ({}, {}, {}), got ({}, {}, {})",
+        diff_x,
+        diff_y,
+        flip,
+        x,
+        y,
+        f
+    );
+}
+
+#[test]
+fn the_player_heads_to_the_finger() {
+    let mut app = touch_app(false);
+    // 300 pixels right of the centre
+    touch(&mut app, TouchPhase::Started, 900.0, 400.0);
+    assert_input(&mut app, PLAYER_SPEED, 0.0, false);
+    touch(&mut app, TouchPhase::Moved, 300.0, 400.0);
+    assert_input(&mut app, -PLAYER_SPEED, 0.0, true);
+    touch(&mut app, TouchPhase::Ended, 300.0, 400.0);
+    assert_input(&mut app, 0.0, 0.0, false);
+}
+
+#[test]
+fn relative_steering_follows_the_finger_movement() {
+    let mut app = touch_app(true);
+    // touching anywhere doesn't move the player
+    touch(&mut app, TouchPhase::Started, 900.0, 400.0);
+    assert_input(&mut app, 0.0, 0.0, false);
+    // 100 pixels right and up
+    touch(&mut app, TouchPhase::Moved, 1000.0, 300.0);
+    let diagonal = PLAYER_SPEED / 2f32.sqrt();
+    assert_input(&mut app, diagonal, diagonal, false);
+    touch(&mut app, TouchPhase::Ended, 1000.0, 300.0);
+    assert_input(&mut app, 0.0, 0.0, false);
+}
@@ -0,0 +1,82 @@
+mod common;
+
+use {
+    bevy::prelude::*,
+    common::headless_app,
+    dodge_the_bevy::{
+        enemy::Enemy, pool::EnemyPool, replay::ReplayFrame, session::GameSession, AppState,
+        RestartEvent,
+    },
+};
+
+const FRAMES: usize = 3000;
+
+fn current_state(app: &App) -> AppState {
+    app.world.resource::<State<AppState>>().current().clone()
+}
+
+#[test]
+fn thousands_of_frames_keep_the_score_and_states_consistent() {
+    // the player stands still
+    let mut app = headless_app(7, ReplayFrame::default(), FRAMES);
+    assert_eq!(current_state(&app), AppState::Game);
+
+    let mut run = app.world.resource::<GameSession>().run;
+    let mut last_max = 0.0;
+    let mut best = 0.0f32;
+    let mut game_overs = 0;
+    for _ in 0..FRAMES {
+        app.update();
+        let session = app.world.resource::<GameSession>();
+        assert!(0.0 <= session.score, "negative score {}", session.score);
+        assert!(session.score <= session.max_score);
+        assert!(
+            last_max <= session.max_score,
+            "the best score of a run went down"
+        );
+        last_max = session.max_score;
+        best = best.max(session.max_score);
+
+        let capacity = app.world.resource::<EnemyPool>().capacity;
+        let enemies = app
+            .world
+            .query_filtered::<Entity, With<Enemy>>()
+            .iter(&app.world)
+            .count();
+        assert!(enemies <= capacity, "{} enemies over {}", enemies, capacity);
+
+        match current_state(&app) {
+            AppState::Game => (),
+            AppState::Restart => {
+                // only a hit ends the game
+                assert!(0 < app.world.resource::<GameSession>().hits);
+                game_overs += 1;
+
+                app.world.send_event(RestartEvent);
+                app.update();
+                assert_eq!(current_state(&app), AppState::Game);
+                assert_eq!(
+                    app.world
+                        .query_filtered::<Entity, With<Enemy>>()
+                        .iter(&app.world)
+                        .count(),
+                    0,
+                    "the enemies of the last game are still there"
+                );
+                let session = app.world.resource::<GameSession>();
+                assert_eq!(session.run, run + 1);
+                assert_eq!(session.score, 0.0);
+                run = session.run;
+                last_max = session.max_score;
+            }
+            state => panic!("unexpected state {:?}", state),
+        }
+    }
+    // five points a second while alive
+    assert!(0.0 < best, "the score never went up");
+    assert_eq!(
+        app.world.resource::<GameSession>().run,
+        1 + game_overs,
+        "a run started without a game over"
+    );
+}
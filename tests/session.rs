@@ -0,0 +1,129 @@
+mod common;
+
+use {
+    bevy::prelude::*,
+    dodge_the_bevy::{
+        character::{Character, SpawnTimer},
+        collision::HitRecovery,
+        damage::Vitality,
+        director::WaveDirector,
+        enemy::Enemy,
+        player::Player,
+        pool::EnemyPool,
+        replay::ReplayFrame,
+        session::GameSession,
+        AppState, GameOverEvent, RestartEvent,
+    },
+};
+
+/// Running to the right at every tick of `frames` frames
+fn headless_app(frames: usize) -> App {
+    common::headless_app(
+        42,
+        ReplayFrame {
+            diff_x: 600.0,
+            ..Default::default()
+        },
+        frames,
+    )
+}
+
+/// Everything a run starts from but its number and seed
+fn snapshot(app: &mut App) -> String {
+    let world = &mut app.world;
+    let session = world.resource::<GameSession>();
+    let session = format!(
+        "score {} max {} elapsed {} wave {} hits {}",
+        session.score, session.max_score, session.elapsed, session.wave, session.hits
+    );
+    let director = world.resource::<WaveDirector>();
+    let mut mix = director.current.mix.iter().collect::<Vec<_>>();
+    mix.sort_by(|a, b| a.0.cmp(b.0));
+    let director = format!(
+        "wave {} elapsed {} since spawn {} due {}; every {} up to {} at {} of {:?}",
+        director.wave,
+        director.elapsed,
+        director.since_spawn,
+        director.spawn_due,
+        director.current.spawn_interval,
+        director.current.max_enemies,
+        director.current.speed_scale,
+        mix
+    );
+    let live = world.resource::<EnemyPool>().stats().live;
+    let enemies = world
+        .query_filtered::<Entity, With<Enemy>>()
+        .iter(world)
+        .count();
+    let player = world
+        .query_filtered::<(
+            &Character,
+            &Transform,
+            &SpawnTimer,
+            &HitRecovery,
+            &Vitality,
+        ), With<Player>>()
+        .iter(world)
+        .map(|(character, trans, timer, recovery, vitality)| {
+            format!(
+                "at ({}, {}) from ({}, {}) moving ({}, {}) flip {}; translation {}; timer {:?}; {:?}; {:?}",
+                character.trans_x,
+                character.trans_y,
+                character.prev_x,
+                character.prev_y,
+                character.diff_x,
+                character.diff_y,
+                character.flip,
+                trans.translation,
+                timer.elapsed(),
+                recovery,
+                vitality
+            )
+        })
+        .collect::<Vec<String>>();
+    format!(
+        "{}\n{}\nlive {} enemies {}\n{:?}",
+        session, director, live, enemies, player
+    )
+}
+
+#[test]
+fn consecutive_runs_start_identically() {
+    // long enough to steer the second run too
+    let mut app = headless_app(240);
+    assert_eq!(
+        app.world.resource::<State<AppState>>().current(),
+        &AppState::Game
+    );
+    let first = snapshot(&mut app);
+    let first_seed = app.world.resource::<GameSession>().seed;
+
+    // play half the replay, and leave some marks
+    for _ in 0..120 {
+        app.update();
+    }
+    app.world.resource_mut::<GameSession>().score = 0.5;
+    assert_ne!(
+        snapshot(&mut app),
+        first,
+        "the first run didn't go anywhere"
+    );
+
+    app.world.send_event(GameOverEvent);
+    app.update();
+    assert_eq!(
+        app.world.resource::<State<AppState>>().current(),
+        &AppState::Restart
+    );
+    app.world.send_event(RestartEvent);
+    app.update();
+    assert_eq!(
+        app.world.resource::<State<AppState>>().current(),
+        &AppState::Game
+    );
+
+    assert_eq!(snapshot(&mut app), first);
+    let session = app.world.resource::<GameSession>();
+    assert_eq!(session.run, 2);
+    assert_ne!(session.seed, first_seed);
+}